@@ -47,7 +47,7 @@ async function configureWETHAsset() {
         const contract = new StellarSdk.Contract(LENDING_POOL_CONTRACT);
         
         const operation = contract.call(
-            'configure_asset',
+            'list_asset',
             StellarSdk.nativeToScVal(assetConfig.asset, { type: 'symbol' }),
             StellarSdk.nativeToScVal(assetConfig.ltv_ratio, { type: 'u32' }),
             StellarSdk.nativeToScVal(assetConfig.liquidation_threshold, { type: 'u32' }),
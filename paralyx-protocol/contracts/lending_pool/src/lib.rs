@@ -1,30 +1,59 @@
 #![no_std]
 
 use soroban_sdk::{
-    contract, contractimpl, contracttype, Address, Env, Symbol,
-    symbol_short, vec
+    contract, contractclient, contractimpl, contracttype, token, Address, Bytes, Env,
+    Symbol, Vec, symbol_short, vec
+};
+use paralyx_math::{
+    bps_mul_ceil, bps_mul_floor, fixed_div_floor, fixed_mul_ceil, fixed_mul_floor, mul_div_ceil,
+    mul_div_floor, to_bps_floor, BPS_DENOMINATOR,
 };
 
 pub(crate) const DAY_IN_LEDGERS: u32 = 17280;
 pub(crate) const INSTANCE_BUMP_AMOUNT: u32 = 7 * DAY_IN_LEDGERS;
 pub(crate) const INSTANCE_LIFETIME_THRESHOLD: u32 = INSTANCE_BUMP_AMOUNT - DAY_IN_LEDGERS;
 
+pub(crate) const SECONDS_PER_YEAR: u64 = 31_536_000;
+// Rates are stored as percentages with 7 decimals (5_0000000 = 5%)
+pub(crate) const RATE_DENOMINATOR: i128 = 100 * 10_000_000;
+
+pub(crate) const DEFAULT_FLASH_LOAN_FEE: u32 = 9; // 0.09%
+pub(crate) const DEFAULT_LIQUIDATION_BONUS: u32 = 500; // 5%
+pub(crate) const CLOSE_FACTOR: u32 = 5000; // Max 50% of a debt repaid per liquidation
+pub(crate) const DEFAULT_LIQUIDATION_GRACE_PERIOD: u64 = 3600; // 1 hour
+pub(crate) const PRICE_DECIMALS: u32 = 7;
+pub(crate) const SEP40_OUTAGE_RECORDS: u32 = 24; // SEP-40 history scanned for the latest outage
+
 // Storage keys
 #[derive(Clone)]
 #[contracttype]
 pub enum DataKey {
     Admin,
+    RiskAdmin,
     STokenContract,
     PriceOracle,
     Asset(Symbol),                    // Asset configuration
+    AssetList,                        // All listed assets
     UserCollateral(Address, Symbol),  // User's collateral amount for an asset
     UserDebt(Address, Symbol),       // User's debt amount for an asset
     TotalSupplied(Symbol),           // Total amount supplied to the pool
     TotalBorrowed(Symbol),           // Total amount borrowed from the pool
+    TotalCollateral(Symbol),         // Total amount deposited as collateral
     UtilizationRate(Symbol),         // Current utilization rate (borrowed/supplied)
     BorrowRate(Symbol),              // Current borrow interest rate
     SupplyRate(Symbol),              // Current supply interest rate
     LastUpdate(Symbol),              // Last update timestamp for interest accrual
+    EModeCategory(u32),              // Efficiency mode category parameters
+    UserEMode(Address),              // User's selected efficiency mode category
+    IsolatedDebt(Symbol, Symbol),    // Outstanding amount of a borrowed asset backed by an isolated asset
+    UserIsolatedAsset(Address),      // Isolated asset backing the user's isolated debt
+    UserIsolatedDebt(Address, Symbol), // User's debt in an asset counted against the isolated asset's ceiling
+    AssetToken(Symbol),              // Token contract holding the asset's liquidity
+    Reserves(Symbol),                // Protocol reserves accumulated from fees
+    FlashLoanFee,                    // Flash loan fee in basis points
+    FlashLoanActive,                 // Reentrancy guard for flash loans
+    LiquidationGracePeriod,          // Seconds liquidations stay paused after a feed resumes
+    PriceSource(Symbol),             // Where an asset's price is read from
 }
 
 // Asset configuration
@@ -36,6 +65,90 @@ pub struct AssetConfig {
     pub reserve_factor: u32,         // Reserve factor for protocol fees (e.g., 1000 = 10%)
     pub is_active: bool,             // Whether asset is active for lending/borrowing
     pub is_collateral: bool,         // Whether asset can be used as collateral
+    pub supply_cap: i128,            // Max supplied + collateral amount (0 = uncapped)
+    pub borrow_cap: i128,            // Max borrowed amount (0 = uncapped)
+    pub emode_category: u32,         // Efficiency mode category (0 = none)
+    pub debt_ceiling: i128,          // USD debt ceiling when used as isolated collateral (0 = not isolated)
+    pub borrowable_in_isolation: bool, // Whether isolated collateral can borrow this asset
+    pub liquidation_bonus: u32,      // Extra collateral paid to liquidators (e.g., 500 = 5%)
+}
+
+// Efficiency mode category for correlated assets (e.g. ETH-like LSDs)
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EModeCategory {
+    pub ltv_ratio: u32,              // LTV applied to assets of this category
+    pub liquidation_threshold: u32,  // Liquidation threshold applied to assets of this category
+    pub liquidation_bonus: u32,      // Liquidation bonus (e.g., 100 = 1%)
+    pub price_source: EModePriceSource, // Price source for assets in the category
+}
+
+// Where assets in an efficiency mode category take their price from
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum EModePriceSource {
+    AssetPrice,                      // Each asset uses its own oracle price
+    Shared(Symbol),                  // All assets use the price of a shared asset (e.g. WETH)
+}
+
+// Reserve state for an asset, including interest accrued since the last update
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ReserveData {
+    pub total_supplied: i128,
+    pub total_borrowed: i128,
+    pub total_collateral: i128,
+    pub utilization_rate: u32,
+    pub borrow_rate: i128,
+    pub supply_rate: i128,
+    pub supply_cap: i128,
+    pub borrow_cap: i128,
+    pub last_update: u64,
+}
+
+// Asset identifier used by SEP-40 oracles
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum OracleAsset {
+    Stellar(Address),
+    Other(Symbol),
+}
+
+// Source the oracle read a price from
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum OracleSource {
+    Local,
+    Sep40(Address, OracleAsset),
+}
+
+// Price reported by the oracle with its confidence interval and freshness
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OraclePriceStatus {
+    pub price: i128,            // Price in USD with 7 decimals
+    pub conf: i128,             // Confidence interval with 7 decimals
+    pub timestamp: u64,
+    pub is_fresh: bool,         // Whether the price is safe to act on
+    pub source: OracleSource,
+    pub resumed_at: u64,        // When the feed last recovered from an outage (0 if never)
+}
+
+// Price reported by a SEP-40 oracle
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Sep40PriceData {
+    pub price: i128,
+    pub timestamp: u64,
+}
+
+// Where the pool reads an asset's price from
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum PriceSourceConfig {
+    Paralyx(Address, Symbol, u32),          // Paralyx price oracle, asset symbol on it, price decimals
+    Sep40(Address, OracleAsset, u32, u64),  // SEP-40 oracle, asset key on it, price decimals, max price age in seconds
+    Constant(i128, u32),                    // Fixed price for pegged test assets, price decimals
 }
 
 // User account data
@@ -46,6 +159,33 @@ pub struct UserAccountData {
     pub total_debt_usd: i128,
     pub ltv: u32,
     pub health_factor: i128,
+    pub borrow_limit_usd: i128,
+    pub emode_category: u32,
+    pub isolation_mode: bool,
+    pub prices_fresh: bool,      // Whether every price used above is fresh
+    pub prices_resumed_at: u64,  // Latest recovery from an outage among those prices
+}
+
+/// Interface that flash loan receivers must implement. The receiver gets the
+/// borrowed amount before the call and must send back `amount + fee` to the
+/// pool before returning. `initiator` is the address that authorized the loan.
+#[contractclient(name = "FlashLoanReceiverClient")]
+pub trait FlashLoanReceiver {
+    fn execute_operation(env: Env, initiator: Address, asset: Symbol, amount: i128, fee: i128, params: Bytes) -> bool;
+}
+
+/// Oracle interface the pool reads prices from, implemented by the Paralyx
+/// price oracle.
+#[contractclient(name = "PriceOracleClient")]
+pub trait PriceOracle {
+    fn get_price_status(env: Env, asset: Symbol) -> OraclePriceStatus;
+}
+
+/// Subset of the SEP-40 oracle interface used for external price feeds.
+#[contractclient(name = "Sep40Client")]
+pub trait Sep40Oracle {
+    fn lastprice(env: Env, asset: OracleAsset) -> Option<Sep40PriceData>;
+    fn prices(env: Env, asset: OracleAsset, records: u32) -> Option<Vec<Sep40PriceData>>;
 }
 
 #[contract]
@@ -69,8 +209,8 @@ impl LendingPool {
         env.storage().instance().set(&DataKey::PriceOracle, &price_oracle);
     }
 
-    /// Configure an asset for lending and borrowing
-    pub fn configure_asset(
+    /// List a new asset for lending and borrowing
+    pub fn list_asset(
         env: Env,
         asset: Symbol,
         ltv_ratio: u32,
@@ -80,16 +220,24 @@ impl LendingPool {
         let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
         admin.require_auth();
 
-        if ltv_ratio > 9500 || liquidation_threshold > 9500 || reserve_factor > 5000 {
-            panic!("invalid configuration parameters");
+        if env.storage().instance().has(&DataKey::Asset(asset.clone())) {
+            panic!("asset already listed");
         }
 
+        Self::validate_risk_params(ltv_ratio, liquidation_threshold, reserve_factor);
+
         let config = AssetConfig {
             ltv_ratio,
             liquidation_threshold,
             reserve_factor,
             is_active: true,
             is_collateral: true,
+            supply_cap: 0,
+            borrow_cap: 0,
+            emode_category: 0,
+            debt_ceiling: 0,
+            borrowable_in_isolation: false,
+            liquidation_bonus: DEFAULT_LIQUIDATION_BONUS,
         };
 
         env.storage().instance().set(&DataKey::Asset(asset.clone()), &config);
@@ -100,9 +248,241 @@ impl LendingPool {
         env.storage().instance().set(&DataKey::SupplyRate(asset.clone()), &1_0000000i128); // 1% base rate
         env.storage().instance().set(&DataKey::LastUpdate(asset.clone()), &env.ledger().timestamp());
 
+        let mut assets: Vec<Symbol> = env.storage().instance()
+            .get(&DataKey::AssetList)
+            .unwrap_or(vec![&env]);
+        assets.push_back(asset.clone());
+        env.storage().instance().set(&DataKey::AssetList, &assets);
+
+        env.events().publish((symbol_short!("asset_lst"), asset), config);
+    }
+
+    /// Update the risk parameters of a listed asset without touching pool state
+    pub fn update_asset_config(
+        env: Env,
+        asset: Symbol,
+        ltv_ratio: u32,
+        liquidation_threshold: u32,
+        reserve_factor: u32,
+        is_active: bool,
+        is_collateral: bool
+    ) {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+
+        let mut config = Self::get_asset_config(env.clone(), asset.clone());
+
+        Self::validate_risk_params(ltv_ratio, liquidation_threshold, reserve_factor);
+        Self::validate_liquidation_bonus(liquidation_threshold, config.liquidation_bonus);
+
+        config.ltv_ratio = ltv_ratio;
+        config.liquidation_threshold = liquidation_threshold;
+        config.reserve_factor = reserve_factor;
+        config.is_active = is_active;
+        config.is_collateral = is_collateral;
+
+        env.storage().instance().set(&DataKey::Asset(asset.clone()), &config);
+
         env.events().publish((symbol_short!("asset_cfg"), asset), config);
     }
 
+    /// Configure isolation mode for an asset (admin only, 0 debt ceiling = not isolated)
+    pub fn set_asset_isolation(
+        env: Env,
+        asset: Symbol,
+        debt_ceiling: i128,
+        borrowable_in_isolation: bool
+    ) {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+
+        if debt_ceiling < 0 {
+            panic!("debt ceiling must not be negative");
+        }
+
+        let mut config = Self::get_asset_config(env.clone(), asset.clone());
+        config.debt_ceiling = debt_ceiling;
+        config.borrowable_in_isolation = borrowable_in_isolation;
+        env.storage().instance().set(&DataKey::Asset(asset.clone()), &config);
+
+        env.events().publish((symbol_short!("iso_set"), asset), (debt_ceiling, borrowable_in_isolation));
+    }
+
+    /// Set the bonus paid to liquidators seizing an asset (admin only)
+    pub fn set_liquidation_bonus(env: Env, asset: Symbol, liquidation_bonus: u32) {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+
+        let mut config = Self::get_asset_config(env.clone(), asset.clone());
+        Self::validate_liquidation_bonus(config.liquidation_threshold, liquidation_bonus);
+
+        config.liquidation_bonus = liquidation_bonus;
+        env.storage().instance().set(&DataKey::Asset(asset.clone()), &config);
+
+        env.events().publish((symbol_short!("liq_bonus"), asset), liquidation_bonus);
+    }
+
+    /// Set the token contract holding an asset's liquidity (admin only)
+    pub fn set_asset_token(env: Env, asset: Symbol, token: Address) {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+
+        Self::get_asset_config(env.clone(), asset.clone());
+
+        env.storage().instance().set(&DataKey::AssetToken(asset.clone()), &token);
+        env.events().publish((symbol_short!("token_set"), asset), token);
+    }
+
+    /// Set where an asset's price is read from (admin only)
+    pub fn set_price_source(env: Env, asset: Symbol, source: PriceSourceConfig) {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+
+        Self::get_asset_config(env.clone(), asset.clone());
+
+        let decimals = match &source {
+            PriceSourceConfig::Paralyx(_, _, decimals) => *decimals,
+            PriceSourceConfig::Sep40(_, _, decimals, max_age) => {
+                if *max_age == 0 {
+                    panic!("max age must be positive");
+                }
+                *decimals
+            }
+            PriceSourceConfig::Constant(price, decimals) => {
+                if *price <= 0 {
+                    panic!("price must be positive");
+                }
+                *decimals
+            }
+        };
+        if decimals > 18 {
+            panic!("price decimals too large");
+        }
+
+        env.storage().instance().set(&DataKey::PriceSource(asset.clone()), &source);
+        env.events().publish((symbol_short!("price_src"), asset), source);
+    }
+
+    /// Set the flash loan fee in basis points (admin only)
+    pub fn set_flash_loan_fee(env: Env, fee: u32) {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+
+        if fee > 1000 {
+            panic!("flash loan fee too high");
+        }
+
+        env.storage().instance().set(&DataKey::FlashLoanFee, &fee);
+        env.events().publish((symbol_short!("fl_fee"),), fee);
+    }
+
+    /// Set the risk admin allowed to adjust caps (admin only)
+    pub fn set_risk_admin(env: Env, risk_admin: Address) {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+
+        env.storage().instance().set(&DataKey::RiskAdmin, &risk_admin);
+        env.events().publish((symbol_short!("risk_adm"),), risk_admin);
+    }
+
+    /// Get the risk admin (defaults to the admin)
+    pub fn get_risk_admin(env: Env) -> Address {
+        env.storage().instance()
+            .get(&DataKey::RiskAdmin)
+            .unwrap_or_else(|| env.storage().instance().get(&DataKey::Admin).unwrap())
+    }
+
+    /// Set supply and borrow caps for an asset (risk admin only, 0 = uncapped)
+    pub fn set_asset_caps(env: Env, asset: Symbol, supply_cap: i128, borrow_cap: i128) {
+        let risk_admin = Self::get_risk_admin(env.clone());
+        risk_admin.require_auth();
+
+        if supply_cap < 0 || borrow_cap < 0 {
+            panic!("caps must not be negative");
+        }
+
+        let mut config = Self::get_asset_config(env.clone(), asset.clone());
+        config.supply_cap = supply_cap;
+        config.borrow_cap = borrow_cap;
+        env.storage().instance().set(&DataKey::Asset(asset.clone()), &config);
+
+        env.events().publish((symbol_short!("caps_set"), asset), (supply_cap, borrow_cap));
+    }
+
+    /// Set how long liquidations stay paused after an oracle outage (risk admin only)
+    pub fn set_liquidation_grace_period(env: Env, grace_period: u64) {
+        let risk_admin = Self::get_risk_admin(env.clone());
+        risk_admin.require_auth();
+
+        env.storage().instance().set(&DataKey::LiquidationGracePeriod, &grace_period);
+
+        env.events().publish((symbol_short!("grace_set"),), grace_period);
+    }
+
+    /// Create or update an efficiency mode category (admin only)
+    pub fn set_emode_category(env: Env, category: u32, emode: EModeCategory) {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+
+        if category == 0 {
+            panic!("category 0 is reserved");
+        }
+
+        if emode.ltv_ratio >= emode.liquidation_threshold || emode.liquidation_threshold > 9900 {
+            panic!("invalid configuration parameters");
+        }
+
+        Self::validate_liquidation_bonus(emode.liquidation_threshold, emode.liquidation_bonus);
+
+        env.storage().instance().set(&DataKey::EModeCategory(category), &emode);
+
+        env.events().publish((symbol_short!("emode_cfg"), category), emode);
+    }
+
+    /// Assign an asset to an efficiency mode category (admin only, 0 = none)
+    pub fn set_asset_emode_category(env: Env, asset: Symbol, category: u32) {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+
+        if category != 0 {
+            Self::get_emode_category(env.clone(), category);
+        }
+
+        let mut config = Self::get_asset_config(env.clone(), asset.clone());
+        config.emode_category = category;
+        env.storage().instance().set(&DataKey::Asset(asset.clone()), &config);
+
+        env.events().publish((symbol_short!("asset_em"), asset), category);
+    }
+
+    /// Opt into an efficiency mode category (0 = leave efficiency mode)
+    pub fn set_user_emode(env: Env, user: Address, category: u32) {
+        user.require_auth();
+
+        if category != 0 {
+            Self::get_emode_category(env.clone(), category);
+
+            // Every borrowed asset must belong to the category
+            for asset in Self::get_assets(env.clone()).iter() {
+                let debt: i128 = env.storage().instance()
+                    .get(&DataKey::UserDebt(user.clone(), asset.clone()))
+                    .unwrap_or(0i128);
+                if debt > 0 && Self::get_asset_config(env.clone(), asset).emode_category != category {
+                    panic!("borrowed asset not in emode category");
+                }
+            }
+        }
+
+        env.storage().instance().set(&DataKey::UserEMode(user.clone()), &category);
+
+        let account_data = Self::get_user_account_data(env.clone(), user.clone());
+        if account_data.health_factor < 1_0000000i128 {
+            panic!("emode change would cause liquidation");
+        }
+
+        env.events().publish((symbol_short!("user_em"), user), category);
+    }
+
     /// Deposit asset to earn interest (supply to pool)
     pub fn deposit(env: Env, user: Address, asset: Symbol, amount: i128) {
         user.require_auth();
@@ -119,6 +499,8 @@ impl LendingPool {
             panic!("amount must be positive");
         }
 
+        Self::check_supply_cap(&env, &asset, &config, amount);
+
         // Update interest rates
         Self::update_interest_rates(env.clone(), asset.clone());
 
@@ -155,6 +537,8 @@ impl LendingPool {
             panic!("amount must be positive");
         }
 
+        Self::check_supply_cap(&env, &asset, &config, amount);
+
         // Update interest rates
         Self::update_interest_rates(env.clone(), asset.clone());
 
@@ -231,6 +615,9 @@ impl LendingPool {
             panic!("amount must be positive");
         }
 
+        Self::check_supply_cap(&env, &asset, &config, amount);
+        Self::check_isolated_collateral(&env, &user, &asset, &config);
+
         // Update user's collateral
         let mut user_collateral: i128 = env.storage().instance()
             .get(&DataKey::UserCollateral(user.clone(), asset.clone()))
@@ -238,9 +625,49 @@ impl LendingPool {
         user_collateral = user_collateral + amount;
         env.storage().instance().set(&DataKey::UserCollateral(user.clone(), asset.clone()), &user_collateral);
 
+        // Update total collateral
+        let total_collateral: i128 = env.storage().instance()
+            .get(&DataKey::TotalCollateral(asset.clone()))
+            .unwrap_or(0i128);
+        env.storage().instance().set(&DataKey::TotalCollateral(asset.clone()), &(total_collateral + amount));
+
         env.events().publish((symbol_short!("coll_dep"), user, asset), amount);
     }
 
+    /// Withdraw collateral, keeping the position healthy. Blocked while any
+    /// price backing the position is stale.
+    pub fn withdraw_collateral(env: Env, user: Address, asset: Symbol, amount: i128) {
+        user.require_auth();
+
+        if amount <= 0 {
+            panic!("amount must be positive");
+        }
+
+        let user_collateral: i128 = env.storage().instance()
+            .get(&DataKey::UserCollateral(user.clone(), asset.clone()))
+            .unwrap_or(0i128);
+        if amount > user_collateral {
+            panic!("insufficient collateral");
+        }
+
+        env.storage().instance().set(&DataKey::UserCollateral(user.clone(), asset.clone()), &(user_collateral - amount));
+
+        let total_collateral: i128 = env.storage().instance()
+            .get(&DataKey::TotalCollateral(asset.clone()))
+            .unwrap_or(0i128);
+        env.storage().instance().set(&DataKey::TotalCollateral(asset.clone()), &(total_collateral - amount));
+
+        let account_data = Self::get_user_account_data(env.clone(), user.clone());
+        if !account_data.prices_fresh {
+            panic!("stale price");
+        }
+        if account_data.health_factor < 1_0000000i128 {
+            panic!("withdrawal would cause liquidation");
+        }
+
+        env.events().publish((symbol_short!("coll_wd"), user, asset), amount);
+    }
+
     /// Borrow asset against collateral
     pub fn borrow(env: Env, user: Address, asset: Symbol, amount: i128) {
         user.require_auth();
@@ -270,6 +697,48 @@ impl LendingPool {
             panic!("insufficient liquidity");
         }
 
+        let emode: u32 = env.storage().instance()
+            .get(&DataKey::UserEMode(user.clone()))
+            .unwrap_or(0u32);
+        if emode != 0 && config.emode_category != emode {
+            panic!("borrowed asset not in emode category");
+        }
+
+        if let Some(isolated_asset) = Self::get_isolated_collateral(&env, &user) {
+            if !config.borrowable_in_isolation {
+                panic!("asset not borrowable in isolation mode");
+            }
+
+            let isolated_config = Self::get_asset_config(env.clone(), isolated_asset.clone());
+            let debt_usd = fixed_mul_ceil(amount, Self::get_asset_price(&env, asset.clone()));
+            if Self::get_isolated_debt(env.clone(), isolated_asset.clone()) + debt_usd > isolated_config.debt_ceiling {
+                panic!("debt ceiling exceeded");
+            }
+
+            // Debt stays attributed to the isolated asset it was borrowed against
+            let user_isolated_asset: Option<Symbol> = env.storage().instance()
+                .get(&DataKey::UserIsolatedAsset(user.clone()));
+            if user_isolated_asset.is_some_and(|backing| backing != isolated_asset) {
+                panic!("repay isolated debt first");
+            }
+            env.storage().instance().set(&DataKey::UserIsolatedAsset(user.clone()), &isolated_asset);
+
+            let key = DataKey::IsolatedDebt(isolated_asset, asset.clone());
+            let isolated_debt: i128 = env.storage().instance().get(&key).unwrap_or(0i128);
+            env.storage().instance().set(&key, &(isolated_debt + amount));
+
+            let user_key = DataKey::UserIsolatedDebt(user.clone(), asset.clone());
+            let user_isolated_debt: i128 = env.storage().instance().get(&user_key).unwrap_or(0i128);
+            env.storage().instance().set(&user_key, &(user_isolated_debt + amount));
+        }
+
+        if config.borrow_cap > 0 {
+            let (_, borrowed_with_interest) = Self::accrued_totals(&env, &asset);
+            if borrowed_with_interest + amount > config.borrow_cap {
+                panic!("borrow cap exceeded");
+            }
+        }
+
         // Update user's debt
         let mut user_debt: i128 = env.storage().instance()
             .get(&DataKey::UserDebt(user.clone(), asset.clone()))
@@ -281,8 +750,14 @@ impl LendingPool {
         let new_total_borrowed = total_borrowed + amount;
         env.storage().instance().set(&DataKey::TotalBorrowed(asset.clone()), &new_total_borrowed);
 
-        // Check health factor after borrow
+        // Check borrowing power and health factor after borrow
         let account_data = Self::get_user_account_data(env.clone(), user.clone());
+        if !account_data.prices_fresh {
+            panic!("stale price");
+        }
+        if account_data.total_debt_usd > account_data.borrow_limit_usd {
+            panic!("insufficient collateral");
+        }
         if account_data.health_factor < 1_0000000i128 { // Health factor < 1.0
             panic!("borrow would cause liquidation");
         }
@@ -301,6 +776,118 @@ impl LendingPool {
             panic!("amount must be positive");
         }
 
+        let repay_amount = Self::reduce_debt(&env, &user, &asset, amount);
+
+        // Update interest rates
+        Self::update_interest_rates(env.clone(), asset.clone());
+
+        env.events().publish((symbol_short!("repay"), user, asset), repay_amount);
+    }
+
+    /// Repay part of an unhealthy position's debt in exchange for its collateral
+    /// plus a liquidation bonus. The liquidator pays the covered debt in the debt
+    /// asset's token and receives the seized collateral in the collateral asset's token.
+    pub fn liquidate(
+        env: Env,
+        liquidator: Address,
+        user: Address,
+        debt_asset: Symbol,
+        collateral_asset: Symbol,
+        debt_to_cover: i128
+    ) {
+        liquidator.require_auth();
+
+        if debt_to_cover <= 0 {
+            panic!("amount must be positive");
+        }
+
+        let account_data = Self::get_user_account_data(env.clone(), user.clone());
+        if !account_data.prices_fresh {
+            panic!("stale price");
+        }
+
+        // Give users a chance to repay or top up once prices come back
+        let grace_period = Self::get_liquidation_grace_period(env.clone());
+        if account_data.prices_resumed_at > 0
+            && env.ledger().timestamp() < account_data.prices_resumed_at + grace_period
+        {
+            panic!("liquidation grace period active");
+        }
+
+        if account_data.health_factor >= 1_0000000i128 {
+            panic!("position is healthy");
+        }
+
+        let user_debt: i128 = env.storage().instance()
+            .get(&DataKey::UserDebt(user.clone(), debt_asset.clone()))
+            .unwrap_or(0i128);
+        let mut repay_amount = debt_to_cover.min(bps_mul_floor(user_debt, CLOSE_FACTOR));
+        if repay_amount <= 0 {
+            panic!("no debt to cover");
+        }
+
+        // Category bonus applies when the seized collateral belongs to the user's category
+        let config = Self::get_asset_config(env.clone(), collateral_asset.clone());
+        let liquidation_bonus = match env.storage().instance().get::<DataKey, EModeCategory>(&DataKey::EModeCategory(account_data.emode_category)) {
+            Some(category) if config.emode_category == account_data.emode_category => category.liquidation_bonus,
+            _ => config.liquidation_bonus,
+        };
+
+        let user_collateral: i128 = env.storage().instance()
+            .get(&DataKey::UserCollateral(user.clone(), collateral_asset.clone()))
+            .unwrap_or(0i128);
+
+        let collateral_value = Self::convert_amount(&env, &debt_asset, &collateral_asset, repay_amount);
+        let mut seized = bps_mul_floor(collateral_value, 10000 + liquidation_bonus);
+        if seized > user_collateral {
+            // Not enough collateral: cover proportionally less debt
+            repay_amount = mul_div_floor(repay_amount, user_collateral, seized);
+            seized = user_collateral;
+        }
+
+        let repay_amount = Self::reduce_debt(&env, &user, &debt_asset, repay_amount);
+
+        env.storage().instance().set(&DataKey::UserCollateral(user.clone(), collateral_asset.clone()), &(user_collateral - seized));
+        let total_collateral: i128 = env.storage().instance()
+            .get(&DataKey::TotalCollateral(collateral_asset.clone()))
+            .unwrap_or(0i128);
+        env.storage().instance().set(&DataKey::TotalCollateral(collateral_asset.clone()), &(total_collateral - seized));
+
+        // The liquidator funds the repaid debt and is paid out rather than credited
+        // collateral, so seizing never mixes into the liquidator's own position
+        let pool = env.current_contract_address();
+        token::Client::new(&env, &Self::get_asset_token(env.clone(), debt_asset.clone()))
+            .transfer(&liquidator, &pool, &repay_amount);
+        token::Client::new(&env, &Self::get_asset_token(env.clone(), collateral_asset.clone()))
+            .transfer(&pool, &liquidator, &seized);
+
+        Self::update_interest_rates(env.clone(), debt_asset.clone());
+
+        env.events().publish(
+            (symbol_short!("liquidate"), user, debt_asset),
+            (liquidator, repay_amount, collateral_asset, seized),
+        );
+    }
+
+    // Isolated collateral cannot be combined with any other collateral
+    fn check_isolated_collateral(env: &Env, user: &Address, asset: &Symbol, config: &AssetConfig) {
+        for other in Self::get_assets(env.clone()).iter() {
+            if other == *asset {
+                continue;
+            }
+            let other_collateral: i128 = env.storage().instance()
+                .get(&DataKey::UserCollateral(user.clone(), other.clone()))
+                .unwrap_or(0i128);
+            if other_collateral > 0
+                && (config.debt_ceiling > 0 || Self::get_asset_config(env.clone(), other).debt_ceiling > 0)
+            {
+                panic!("cannot mix isolated collateral");
+            }
+        }
+    }
+
+    // Reduce a user's debt by up to `amount`, returning the amount actually repaid
+    fn reduce_debt(env: &Env, user: &Address, asset: &Symbol, amount: i128) -> i128 {
         let mut user_debt: i128 = env.storage().instance()
             .get(&DataKey::UserDebt(user.clone(), asset.clone()))
             .unwrap_or(0i128);
@@ -317,38 +904,169 @@ impl LendingPool {
         total_borrowed = total_borrowed - repay_amount;
         env.storage().instance().set(&DataKey::TotalBorrowed(asset.clone()), &total_borrowed);
 
-        // Update interest rates
+        // Release the repaid amount from the ceiling of the asset the debt was borrowed
+        // against, even if that collateral is gone or no longer isolated
+        let user_isolated_asset: Option<Symbol> = env.storage().instance()
+            .get(&DataKey::UserIsolatedAsset(user.clone()));
+        if let Some(isolated_asset) = user_isolated_asset {
+            let user_key = DataKey::UserIsolatedDebt(user.clone(), asset.clone());
+            let user_isolated_debt: i128 = env.storage().instance().get(&user_key).unwrap_or(0i128);
+            let released = repay_amount.min(user_isolated_debt);
+            env.storage().instance().set(&user_key, &(user_isolated_debt - released));
+
+            let key = DataKey::IsolatedDebt(isolated_asset, asset.clone());
+            let isolated_debt: i128 = env.storage().instance().get(&key).unwrap_or(0i128);
+            env.storage().instance().set(&key, &(isolated_debt - released).max(0));
+
+            let has_isolated_debt = Self::get_assets(env.clone()).iter().any(|borrowed_asset| {
+                env.storage().instance()
+                    .get::<DataKey, i128>(&DataKey::UserIsolatedDebt(user.clone(), borrowed_asset))
+                    .unwrap_or(0i128) > 0
+            });
+            if !has_isolated_debt {
+                env.storage().instance().remove(&DataKey::UserIsolatedAsset(user.clone()));
+            }
+        }
+
+        repay_amount
+    }
+
+    /// Lend pool liquidity to a receiver contract for the duration of one call
+    pub fn flash_loan(env: Env, initiator: Address, receiver: Address, asset: Symbol, amount: i128, params: Bytes) {
+        initiator.require_auth();
+
+        let config = Self::get_asset_config(env.clone(), asset.clone());
+
+        if !config.is_active {
+            panic!("asset not active");
+        }
+
+        if amount <= 0 {
+            panic!("amount must be positive");
+        }
+
+        if env.storage().instance().has(&DataKey::FlashLoanActive) {
+            panic!("flash loan already in progress");
+        }
+
+        let (total_supplied, total_borrowed, _) = Self::get_pool_info(env.clone(), asset.clone());
+        if amount > total_supplied - total_borrowed {
+            panic!("insufficient liquidity");
+        }
+
+        let token_address: Address = env.storage().instance()
+            .get(&DataKey::AssetToken(asset.clone()))
+            .unwrap_or_else(|| panic!("asset token not set"));
+        let token = token::Client::new(&env, &token_address);
+        let pool = env.current_contract_address();
+
+        let fee = bps_mul_ceil(amount, Self::get_flash_loan_fee(env.clone()));
+        let balance_before = token.balance(&pool);
+
+        env.storage().instance().set(&DataKey::FlashLoanActive, &true);
+
+        token.transfer(&pool, &receiver, &amount);
+
+        let receiver_client = FlashLoanReceiverClient::new(&env, &receiver);
+        if !receiver_client.execute_operation(&initiator, &asset, &amount, &fee, &params) {
+            panic!("flash loan rejected by receiver");
+        }
+
+        if token.balance(&pool) < balance_before + fee {
+            panic!("flash loan not repaid");
+        }
+
+        env.storage().instance().remove(&DataKey::FlashLoanActive);
+
+        // Split the fee between protocol reserves and suppliers
+        let protocol_fee = bps_mul_floor(fee, config.reserve_factor);
+        let reserves = Self::get_reserves(env.clone(), asset.clone());
+        env.storage().instance().set(&DataKey::Reserves(asset.clone()), &(reserves + protocol_fee));
+        env.storage().instance().set(&DataKey::TotalSupplied(asset.clone()), &(total_supplied + fee - protocol_fee));
+
         Self::update_interest_rates(env.clone(), asset.clone());
 
-        env.events().publish((symbol_short!("repay"), user, asset), repay_amount);
+        env.events().publish((symbol_short!("flash"), receiver, asset), (initiator, amount, fee));
     }
 
     /// Get user's account data (collateral, debt, health factor)
     pub fn get_user_account_data(env: Env, user: Address) -> UserAccountData {
-        // This is a simplified implementation
-        // In practice, you'd iterate through all assets to calculate totals
-
-        // For demo, let's assume we're checking XLM collateral and debt
-        let xlm_collateral: i128 = env.storage().instance()
-            .get(&DataKey::UserCollateral(user.clone(), symbol_short!("XLM")))
-            .unwrap_or(0i128);
-        let xlm_debt: i128 = env.storage().instance()
-            .get(&DataKey::UserDebt(user.clone(), symbol_short!("XLM")))
-            .unwrap_or(0i128);
+        let emode: u32 = env.storage().instance()
+            .get(&DataKey::UserEMode(user.clone()))
+            .unwrap_or(0u32);
+        let emode_category: Option<EModeCategory> = if emode != 0 {
+            env.storage().instance().get(&DataKey::EModeCategory(emode))
+        } else {
+            None
+        };
 
-        // Mock USD values (in real implementation, get from price oracle)
-        let xlm_price = 12_0000000i128; // $0.12
-        let total_collateral_usd = xlm_collateral * xlm_price / 10_000_000i128;
-        let total_debt_usd = xlm_debt * xlm_price / 10_000_000i128;
+        // Only the isolated asset counts as collateral while in isolation mode
+        let isolated_asset = Self::get_isolated_collateral(&env, &user);
+
+        let mut total_collateral_usd = 0i128;
+        let mut total_debt_usd = 0i128;
+        let mut borrow_limit_usd = 0i128;
+        let mut weighted_threshold_usd = 0i128;
+        let mut prices_fresh = true;
+        let mut prices_resumed_at = 0u64;
+
+        for asset in Self::get_assets(env.clone()).iter() {
+            let collateral: i128 = env.storage().instance()
+                .get(&DataKey::UserCollateral(user.clone(), asset.clone()))
+                .unwrap_or(0i128);
+            let debt: i128 = env.storage().instance()
+                .get(&DataKey::UserDebt(user.clone(), asset.clone()))
+                .unwrap_or(0i128);
+
+            if collateral == 0 && debt == 0 {
+                continue;
+            }
+
+            let config = Self::get_asset_config(env.clone(), asset.clone());
+
+            // Assets in the user's category use the category parameters and price source
+            let (ltv_ratio, liquidation_threshold, price_asset) = match &emode_category {
+                Some(category) if config.emode_category == emode => (
+                    category.ltv_ratio,
+                    category.liquidation_threshold,
+                    match &category.price_source {
+                        EModePriceSource::Shared(source) => source.clone(),
+                        EModePriceSource::AssetPrice => asset.clone(),
+                    },
+                ),
+                _ => (config.ltv_ratio, config.liquidation_threshold, asset.clone()),
+            };
+
+            // Collateral is valued at the low end and debt at the high end of the interval
+            let price_data = Self::get_price_status(&env, price_asset);
+            prices_fresh &= price_data.is_fresh;
+            prices_resumed_at = prices_resumed_at.max(price_data.resumed_at);
+
+            let counts_as_collateral = match &isolated_asset {
+                Some(isolated) => *isolated == asset,
+                None => true,
+            };
+
+            if collateral > 0 && config.is_collateral && counts_as_collateral {
+                let collateral_usd = fixed_mul_floor(collateral, (price_data.price - price_data.conf).max(0));
+                total_collateral_usd += collateral_usd;
+                borrow_limit_usd += bps_mul_floor(collateral_usd, ltv_ratio);
+                weighted_threshold_usd += bps_mul_floor(collateral_usd, liquidation_threshold);
+            }
+
+            if debt > 0 {
+                total_debt_usd += fixed_mul_ceil(debt, price_data.price + price_data.conf);
+            }
+        }
 
         let ltv = if total_collateral_usd > 0 {
-            ((total_debt_usd * 10000) / total_collateral_usd) as u32
+            mul_div_ceil(total_debt_usd, BPS_DENOMINATOR, total_collateral_usd) as u32
         } else {
             0
         };
 
         let health_factor = if total_debt_usd > 0 {
-            (total_collateral_usd * 8000) / (total_debt_usd * 10000) // 80% liquidation threshold
+            fixed_div_floor(weighted_threshold_usd, total_debt_usd)
         } else {
             i128::MAX
         };
@@ -358,9 +1076,42 @@ impl LendingPool {
             total_debt_usd,
             ltv,
             health_factor,
+            borrow_limit_usd,
+            emode_category: emode,
+            isolation_mode: isolated_asset.is_some(),
+            prices_fresh,
+            prices_resumed_at,
         }
     }
 
+    /// Get outstanding debt backed by an isolated collateral asset, in USD at current prices
+    pub fn get_isolated_debt(env: Env, asset: Symbol) -> i128 {
+        let mut debt_usd = 0i128;
+        for borrowed_asset in Self::get_assets(env.clone()).iter() {
+            let amount: i128 = env.storage().instance()
+                .get(&DataKey::IsolatedDebt(asset.clone(), borrowed_asset.clone()))
+                .unwrap_or(0i128);
+            if amount > 0 {
+                debt_usd += fixed_mul_ceil(amount, Self::get_asset_price(&env, borrowed_asset));
+            }
+        }
+        debt_usd
+    }
+
+    /// Get efficiency mode category parameters
+    pub fn get_emode_category(env: Env, category: u32) -> EModeCategory {
+        env.storage().instance()
+            .get(&DataKey::EModeCategory(category))
+            .unwrap_or_else(|| panic!("emode category not found"))
+    }
+
+    /// Get user's efficiency mode category (0 = none)
+    pub fn get_user_emode(env: Env, user: Address) -> u32 {
+        env.storage().instance()
+            .get(&DataKey::UserEMode(user))
+            .unwrap_or(0u32)
+    }
+
     /// Get asset configuration
     pub fn get_asset_config(env: Env, asset: Symbol) -> AssetConfig {
         env.storage().instance()
@@ -368,6 +1119,79 @@ impl LendingPool {
             .unwrap_or_else(|| panic!("asset not configured"))
     }
 
+    /// Get reserve data for an asset, including caps and accrued interest
+    pub fn get_reserve_data(env: Env, asset: Symbol) -> ReserveData {
+        let config = Self::get_asset_config(env.clone(), asset.clone());
+        let (total_supplied, total_borrowed) = Self::accrued_totals(&env, &asset);
+
+        ReserveData {
+            total_supplied,
+            total_borrowed,
+            total_collateral: env.storage().instance()
+                .get(&DataKey::TotalCollateral(asset.clone()))
+                .unwrap_or(0i128),
+            utilization_rate: env.storage().instance()
+                .get(&DataKey::UtilizationRate(asset.clone()))
+                .unwrap_or(0u32),
+            borrow_rate: env.storage().instance()
+                .get(&DataKey::BorrowRate(asset.clone()))
+                .unwrap_or(0i128),
+            supply_rate: env.storage().instance()
+                .get(&DataKey::SupplyRate(asset.clone()))
+                .unwrap_or(0i128),
+            supply_cap: config.supply_cap,
+            borrow_cap: config.borrow_cap,
+            last_update: env.storage().instance()
+                .get(&DataKey::LastUpdate(asset))
+                .unwrap_or(0u64),
+        }
+    }
+
+    /// Get all listed assets
+    pub fn get_assets(env: Env) -> Vec<Symbol> {
+        env.storage().instance()
+            .get(&DataKey::AssetList)
+            .unwrap_or(vec![&env])
+    }
+
+    /// Get the token contract holding an asset's liquidity
+    pub fn get_asset_token(env: Env, asset: Symbol) -> Address {
+        env.storage().instance()
+            .get(&DataKey::AssetToken(asset))
+            .unwrap_or_else(|| panic!("asset token not set"))
+    }
+
+    /// Get where an asset's price is read from (defaults to the Paralyx oracle)
+    pub fn get_price_source(env: Env, asset: Symbol) -> PriceSourceConfig {
+        env.storage().instance()
+            .get(&DataKey::PriceSource(asset.clone()))
+            .unwrap_or_else(|| {
+                let price_oracle: Address = env.storage().instance().get(&DataKey::PriceOracle).unwrap();
+                PriceSourceConfig::Paralyx(price_oracle, asset, PRICE_DECIMALS)
+            })
+    }
+
+    /// Get how long liquidations stay paused after an oracle outage
+    pub fn get_liquidation_grace_period(env: Env) -> u64 {
+        env.storage().instance()
+            .get(&DataKey::LiquidationGracePeriod)
+            .unwrap_or(DEFAULT_LIQUIDATION_GRACE_PERIOD)
+    }
+
+    /// Get the flash loan fee in basis points
+    pub fn get_flash_loan_fee(env: Env) -> u32 {
+        env.storage().instance()
+            .get(&DataKey::FlashLoanFee)
+            .unwrap_or(DEFAULT_FLASH_LOAN_FEE)
+    }
+
+    /// Get protocol reserves accumulated for an asset
+    pub fn get_reserves(env: Env, asset: Symbol) -> i128 {
+        env.storage().instance()
+            .get(&DataKey::Reserves(asset))
+            .unwrap_or(0i128)
+    }
+
     /// Get pool liquidity info
     pub fn get_pool_info(env: Env, asset: Symbol) -> (i128, i128, u32) {
         let total_supplied: i128 = env.storage().instance()
@@ -384,6 +1208,160 @@ impl LendingPool {
     }
 
     // Internal helper functions
+    fn get_isolated_collateral(env: &Env, user: &Address) -> Option<Symbol> {
+        for asset in Self::get_assets(env.clone()).iter() {
+            let collateral: i128 = env.storage().instance()
+                .get(&DataKey::UserCollateral(user.clone(), asset.clone()))
+                .unwrap_or(0i128);
+            if collateral > 0 && Self::get_asset_config(env.clone(), asset.clone()).debt_ceiling > 0 {
+                return Some(asset);
+            }
+        }
+        None
+    }
+
+    // Latest price even if stale; callers acting on it must check freshness
+    fn get_asset_price(env: &Env, asset: Symbol) -> i128 {
+        Self::get_price_status(env, asset).price
+    }
+
+    // Amounts are 7-decimal pool units, as in the account valuation
+    fn convert_amount(env: &Env, from_asset: &Symbol, to_asset: &Symbol, amount: i128) -> i128 {
+        let to_price = Self::get_asset_price(env, to_asset.clone());
+        if to_price <= 0 {
+            panic!("price not available");
+        }
+        mul_div_floor(amount, Self::get_asset_price(env, from_asset.clone()), to_price)
+    }
+
+    // Price status from the asset's configured source, normalized to 7 decimals
+    fn get_price_status(env: &Env, asset: Symbol) -> OraclePriceStatus {
+        match Self::get_price_source(env.clone(), asset) {
+            PriceSourceConfig::Paralyx(oracle, oracle_asset, decimals) => {
+                let mut status = PriceOracleClient::new(env, &oracle).get_price_status(&oracle_asset);
+                status.price = Self::normalize_price(status.price, decimals, false);
+                status.conf = Self::normalize_price(status.conf, decimals, true);
+                status
+            }
+            PriceSourceConfig::Sep40(oracle, oracle_asset, decimals, max_age) => {
+                // An unreachable external oracle reads as a missing price
+                let client = Sep40Client::new(env, &oracle);
+                let price_data = match client.try_lastprice(&oracle_asset) {
+                    Ok(Ok(Some(price_data))) => price_data,
+                    _ => Sep40PriceData { price: 0, timestamp: 0 },
+                };
+                let resumed_at = Self::sep40_resumed_at(&client, &oracle_asset, max_age);
+                OraclePriceStatus {
+                    price: Self::normalize_price(price_data.price, decimals, false),
+                    conf: 0,
+                    timestamp: price_data.timestamp,
+                    is_fresh: price_data.price > 0
+                        && env.ledger().timestamp() <= price_data.timestamp.saturating_add(max_age),
+                    source: OracleSource::Sep40(oracle, oracle_asset),
+                    resumed_at,
+                }
+            }
+            PriceSourceConfig::Constant(price, decimals) => OraclePriceStatus {
+                price: Self::normalize_price(price, decimals, false),
+                conf: 0,
+                timestamp: env.ledger().timestamp(),
+                is_fresh: true,
+                source: OracleSource::Local,
+                resumed_at: 0,
+            },
+        }
+    }
+
+    // External feeds keep no outage record for us, so the latest recovery is found as
+    // the first price after a gap longer than the max age in the feed's recent history.
+    // Recoveries followed by more than SEP40_OUTAGE_RECORDS updates are not seen.
+    fn sep40_resumed_at(client: &Sep40Client, asset: &OracleAsset, max_age: u64) -> u64 {
+        let history = match client.try_prices(asset, &SEP40_OUTAGE_RECORDS) {
+            Ok(Ok(Some(history))) => history,
+            _ => return 0,
+        };
+        for i in 1..history.len() {
+            let newer = history.get(i - 1).unwrap();
+            if newer.timestamp > history.get(i).unwrap().timestamp.saturating_add(max_age) {
+                return newer.timestamp;
+            }
+        }
+        0
+    }
+
+    fn normalize_price(value: i128, decimals: u32, round_up: bool) -> i128 {
+        if decimals >= PRICE_DECIMALS {
+            let divisor = 10i128.pow(decimals - PRICE_DECIMALS);
+            if round_up {
+                mul_div_ceil(value, 1, divisor)
+            } else {
+                mul_div_floor(value, 1, divisor)
+            }
+        } else {
+            value * 10i128.pow(PRICE_DECIMALS - decimals)
+        }
+    }
+
+    fn check_supply_cap(env: &Env, asset: &Symbol, config: &AssetConfig, amount: i128) {
+        if config.supply_cap == 0 {
+            return;
+        }
+
+        let (supplied_with_interest, _) = Self::accrued_totals(env, asset);
+        let total_collateral: i128 = env.storage().instance()
+            .get(&DataKey::TotalCollateral(asset.clone()))
+            .unwrap_or(0i128);
+
+        if supplied_with_interest + total_collateral + amount > config.supply_cap {
+            panic!("supply cap exceeded");
+        }
+    }
+
+    // Total supplied and borrowed including interest accrued since the last rate update
+    fn accrued_totals(env: &Env, asset: &Symbol) -> (i128, i128) {
+        let total_supplied: i128 = env.storage().instance()
+            .get(&DataKey::TotalSupplied(asset.clone()))
+            .unwrap_or(0i128);
+        let total_borrowed: i128 = env.storage().instance()
+            .get(&DataKey::TotalBorrowed(asset.clone()))
+            .unwrap_or(0i128);
+        let supply_rate: i128 = env.storage().instance()
+            .get(&DataKey::SupplyRate(asset.clone()))
+            .unwrap_or(0i128);
+        let borrow_rate: i128 = env.storage().instance()
+            .get(&DataKey::BorrowRate(asset.clone()))
+            .unwrap_or(0i128);
+        let last_update: u64 = env.storage().instance()
+            .get(&DataKey::LastUpdate(asset.clone()))
+            .unwrap_or(env.ledger().timestamp());
+
+        let elapsed = env.ledger().timestamp().saturating_sub(last_update) as i128;
+        let year = SECONDS_PER_YEAR as i128;
+
+        // Suppliers earn rounded down, borrowers owe rounded up
+        let supply_interest = mul_div_floor(total_supplied, supply_rate * elapsed, RATE_DENOMINATOR * year);
+        let borrow_interest = mul_div_ceil(total_borrowed, borrow_rate * elapsed, RATE_DENOMINATOR * year);
+
+        (total_supplied + supply_interest, total_borrowed + borrow_interest)
+    }
+
+    fn validate_risk_params(ltv_ratio: u32, liquidation_threshold: u32, reserve_factor: u32) {
+        if ltv_ratio > 9500 || liquidation_threshold > 9500 || reserve_factor > 5000 {
+            panic!("invalid configuration parameters");
+        }
+
+        if ltv_ratio >= liquidation_threshold {
+            panic!("ltv must be below liquidation threshold");
+        }
+    }
+
+    // Liquidating at the threshold must not seize more than the collateral
+    fn validate_liquidation_bonus(liquidation_threshold: u32, liquidation_bonus: u32) {
+        if (liquidation_threshold as u64) * (10000 + liquidation_bonus as u64) > 10000 * 10000 {
+            panic!("liquidation bonus too high");
+        }
+    }
+
     fn update_interest_rates(env: Env, asset: Symbol) {
         let total_supplied: i128 = env.storage().instance()
             .get(&DataKey::TotalSupplied(asset.clone()))
@@ -393,7 +1371,7 @@ impl LendingPool {
             .unwrap_or(0i128);
 
         let utilization_rate = if total_supplied > 0 {
-            ((total_borrowed * 10000) / total_supplied) as u32
+            to_bps_floor(total_borrowed, total_supplied) as u32
         } else {
             0
         };
@@ -402,8 +1380,8 @@ impl LendingPool {
         let base_borrow_rate = 2_0000000i128; // 2%
         let rate_slope = 5_0000000i128; // 5%
 
-        let borrow_rate = base_borrow_rate + (rate_slope * utilization_rate as i128) / 10000;
-        let supply_rate = (borrow_rate * utilization_rate as i128) / 10000;
+        let borrow_rate = base_borrow_rate + bps_mul_ceil(rate_slope, utilization_rate);
+        let supply_rate = bps_mul_floor(borrow_rate, utilization_rate);
 
         env.storage().instance().set(&DataKey::UtilizationRate(asset.clone()), &utilization_rate);
         env.storage().instance().set(&DataKey::BorrowRate(asset.clone()), &borrow_rate);
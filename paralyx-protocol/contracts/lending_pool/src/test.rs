@@ -2,8 +2,8 @@
 
 use super::*;
 use soroban_sdk::{
-    testutils::{Address as _, MockAuth, MockAuthInvoke, Events},
-    symbol_short, Address, Env, Symbol, IntoVal
+    testutils::{Address as _, MockAuth, MockAuthInvoke, Events, Ledger},
+    symbol_short, token, Address, Bytes, Env, Symbol, IntoVal
 };
 
 fn create_lending_pool_contract<'a>(e: &Env) -> Address {
@@ -37,15 +37,72 @@ pub struct MockOracle;
 
 #[contractimpl]
 impl MockOracle {
+    pub fn set_price(env: Env, asset: Symbol, price: i128) {
+        env.storage().instance().set(&asset, &price);
+    }
+
     pub fn get_price(env: Env, asset: Symbol) -> i128 {
+        if let Some(price) = env.storage().instance().get(&asset) {
+            return price;
+        }
+
         // Mock prices for testing
         match asset {
             _ if asset == symbol_short!("XLM") => 12_0000000i128, // $0.12
             _ if asset == symbol_short!("USDC") => 1_0000000i128, // $1.00
             _ if asset == symbol_short!("stETH") => 1500_0000000i128, // $1500
+            _ if asset == symbol_short!("WETH") => 1600_0000000i128, // $1600
+            _ if asset == symbol_short!("rETH") => 1500_0000000i128, // $1500
             _ => 1_0000000i128, // Default $1.00
         }
     }
+
+    pub fn set_stale(env: Env, asset: Symbol, stale: bool) {
+        env.storage().instance().set(&(asset, symbol_short!("stale")), &stale);
+    }
+
+    pub fn set_resumed_at(env: Env, asset: Symbol, resumed_at: u64) {
+        env.storage().instance().set(&(asset, symbol_short!("resumed")), &resumed_at);
+    }
+
+    pub fn get_price_status(env: Env, asset: Symbol) -> OraclePriceStatus {
+        // rETH is quoted with a 2% confidence interval
+        let conf = if asset == symbol_short!("rETH") { 30_0000000i128 } else { 0 };
+        let stale: bool = env.storage().instance()
+            .get(&(asset.clone(), symbol_short!("stale")))
+            .unwrap_or(false);
+        OraclePriceStatus {
+            price: Self::get_price(env.clone(), asset.clone()),
+            conf,
+            timestamp: env.ledger().timestamp(),
+            is_fresh: !stale,
+            source: OracleSource::Local,
+            resumed_at: env.storage().instance()
+                .get(&(asset, symbol_short!("resumed")))
+                .unwrap_or(0u64),
+        }
+    }
+}
+
+#[contract]
+pub struct MockSep40Oracle;
+
+#[contractimpl]
+impl MockSep40Oracle {
+    pub fn set_lastprice(env: Env, asset: OracleAsset, price: i128, timestamp: u64) {
+        let mut history: Vec<Sep40PriceData> = Self::prices(env.clone(), asset.clone(), u32::MAX).unwrap_or(Vec::new(&env));
+        history.push_front(Sep40PriceData { price, timestamp });
+        env.storage().instance().set(&asset, &history);
+    }
+
+    pub fn lastprice(env: Env, asset: OracleAsset) -> Option<Sep40PriceData> {
+        Self::prices(env, asset, 1).map(|history| history.get(0).unwrap())
+    }
+
+    pub fn prices(env: Env, asset: OracleAsset, records: u32) -> Option<Vec<Sep40PriceData>> {
+        let history: Vec<Sep40PriceData> = env.storage().instance().get(&asset)?;
+        Some(history.slice(0..records.min(history.len())))
+    }
 }
 
 #[contract]
@@ -71,6 +128,71 @@ fn create_mock_stoken_contract<'a>(e: &Env) -> Address {
     e.register_contract(None, MockSToken {})
 }
 
+#[contract]
+pub struct MockFlashLoanReceiver;
+
+#[contractimpl]
+impl MockFlashLoanReceiver {
+    pub fn init(env: Env, pool: Address, token: Address, repay_fee: bool) {
+        env.storage().instance().set(&symbol_short!("pool"), &pool);
+        env.storage().instance().set(&symbol_short!("token"), &token);
+        env.storage().instance().set(&symbol_short!("repay_fee"), &repay_fee);
+    }
+
+    pub fn execute_operation(env: Env, initiator: Address, _asset: Symbol, amount: i128, fee: i128, _params: Bytes) -> bool {
+        env.storage().instance().set(&symbol_short!("initiator"), &initiator);
+        let pool: Address = env.storage().instance().get(&symbol_short!("pool")).unwrap();
+        let token: Address = env.storage().instance().get(&symbol_short!("token")).unwrap();
+        let repay_fee: bool = env.storage().instance().get(&symbol_short!("repay_fee")).unwrap();
+
+        let repayment = if repay_fee { amount + fee } else { amount };
+        token::Client::new(&env, &token).transfer(&env.current_contract_address(), &pool, &repayment);
+        true
+    }
+
+    pub fn get_initiator(env: Env) -> Address {
+        env.storage().instance().get(&symbol_short!("initiator")).unwrap()
+    }
+}
+
+fn setup_flash_loan<'a>(env: &Env, repay_fee: bool) -> (LendingPoolClient<'a>, Address, Address) {
+    let contract_id = create_lending_pool_contract(env);
+    let client = LendingPoolClient::new(env, &contract_id);
+
+    let admin = Address::generate(env);
+    let price_oracle = create_mock_oracle_contract(env);
+    let s_token_contract = create_mock_stoken_contract(env);
+    let supplier = Address::generate(env);
+
+    client.initialize(&admin, &s_token_contract, &price_oracle);
+
+    let asset_symbol = symbol_short!("USDC");
+    client.list_asset(&asset_symbol, &8000u32, &9000u32, &1000u32);
+
+    let token_address = env.register_stellar_asset_contract(admin.clone());
+    client.set_asset_token(&asset_symbol, &token_address);
+    client.deposit(&supplier, &asset_symbol, &10000_0000000i128);
+    token::StellarAssetClient::new(env, &token_address).mint(&contract_id, &10000_0000000i128);
+
+    // Receiver holds enough to cover the fee
+    let receiver = env.register_contract(None, MockFlashLoanReceiver {});
+    MockFlashLoanReceiverClient::new(env, &receiver).init(&contract_id, &token_address, &repay_fee);
+    token::StellarAssetClient::new(env, &token_address).mint(&receiver, &10_0000000i128);
+
+    (client, receiver, token_address)
+}
+
+// Token contracts backing liquidations, with the liquidator funded to repay debt
+fn set_liquidation_tokens(env: &Env, client: &LendingPoolClient, admin: &Address, liquidator: &Address) -> (Address, Address) {
+    let usdc_token = env.register_stellar_asset_contract(admin.clone());
+    let steth_token = env.register_stellar_asset_contract(admin.clone());
+    client.set_asset_token(&symbol_short!("USDC"), &usdc_token);
+    client.set_asset_token(&symbol_short!("stETH"), &steth_token);
+    token::StellarAssetClient::new(env, &usdc_token).mint(liquidator, &1000_0000000i128);
+    token::StellarAssetClient::new(env, &steth_token).mint(&client.address, &10_0000000i128);
+    (usdc_token, steth_token)
+}
+
 #[test]
 fn test_initialize() {
     let env = Env::default();
@@ -85,7 +207,7 @@ fn test_initialize() {
 }
 
 #[test]
-fn test_configure_asset() {
+fn test_list_asset() {
     let env = Env::default();
     env.mock_all_auths();
 
@@ -105,12 +227,12 @@ fn test_configure_asset() {
             address: &admin,
             invoke: &MockAuthInvoke {
                 contract: &contract_id,
-                fn_name: "configure_asset",
+                fn_name: "list_asset",
                 args: (asset_symbol.clone(), 6000u32, 8000u32, 1000u32).into_val(&env),
                 sub_invokes: &[],
             },
         }
-    ]).configure_asset(&asset_symbol, &6000u32, &8000u32, &1000u32);
+    ]).list_asset(&asset_symbol, &6000u32, &8000u32, &1000u32);
 
     // Verify configuration is set
     let config = client.get_asset_config(&asset_symbol);
@@ -119,6 +241,90 @@ fn test_configure_asset() {
     assert_eq!(config.reserve_factor, 1000u32);
 }
 
+#[test]
+#[should_panic(expected = "asset already listed")]
+fn test_list_asset_twice() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = create_lending_pool_contract(&env);
+    let client = LendingPoolClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let price_oracle = create_mock_oracle_contract(&env);
+    let s_token_contract = create_mock_stoken_contract(&env);
+
+    client.initialize(&admin, &s_token_contract, &price_oracle);
+
+    let asset_symbol = symbol_short!("XLM");
+    client.list_asset(&asset_symbol, &6000u32, &8000u32, &1000u32);
+    client.list_asset(&asset_symbol, &6000u32, &8000u32, &1000u32);
+}
+
+#[test]
+fn test_update_asset_config_preserves_pool_state() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = create_lending_pool_contract(&env);
+    let client = LendingPoolClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let price_oracle = create_mock_oracle_contract(&env);
+    let s_token_contract = create_mock_stoken_contract(&env);
+    let user = Address::generate(&env);
+
+    client.initialize(&admin, &s_token_contract, &price_oracle);
+
+    let asset_symbol = symbol_short!("XLM");
+    client.list_asset(&asset_symbol, &6000u32, &8000u32, &1000u32);
+    client.deposit(&user, &asset_symbol, &1000_0000000i128);
+
+    client.mock_auths(&[
+        MockAuth {
+            address: &admin,
+            invoke: &MockAuthInvoke {
+                contract: &contract_id,
+                fn_name: "update_asset_config",
+                args: (asset_symbol.clone(), 7000u32, 8500u32, 2000u32, true, false).into_val(&env),
+                sub_invokes: &[],
+            },
+        }
+    ]).update_asset_config(&asset_symbol, &7000u32, &8500u32, &2000u32, &true, &false);
+
+    let config = client.get_asset_config(&asset_symbol);
+    assert_eq!(config.ltv_ratio, 7000u32);
+    assert_eq!(config.liquidation_threshold, 8500u32);
+    assert_eq!(config.reserve_factor, 2000u32);
+    assert!(config.is_active);
+    assert!(!config.is_collateral);
+
+    // Supplied liquidity survives the update
+    let (total_supplied, _, _) = client.get_pool_info(&asset_symbol);
+    assert_eq!(total_supplied, 1000_0000000i128);
+    assert_eq!(client.get_assets().len(), 1);
+}
+
+#[test]
+#[should_panic(expected = "ltv must be below liquidation threshold")]
+fn test_update_asset_config_ltv_above_threshold() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = create_lending_pool_contract(&env);
+    let client = LendingPoolClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let price_oracle = create_mock_oracle_contract(&env);
+    let s_token_contract = create_mock_stoken_contract(&env);
+
+    client.initialize(&admin, &s_token_contract, &price_oracle);
+
+    let asset_symbol = symbol_short!("XLM");
+    client.list_asset(&asset_symbol, &6000u32, &8000u32, &1000u32);
+    client.update_asset_config(&asset_symbol, &8000u32, &8000u32, &1000u32, &true, &true);
+}
+
 #[test]
 fn test_pool_info() {
     let env = Env::default();
@@ -183,12 +389,12 @@ fn test_deposit() {
             address: &admin,
             invoke: &MockAuthInvoke {
                 contract: &contract_id,
-                fn_name: "configure_asset",
+                fn_name: "list_asset",
                 args: (asset_symbol.clone(), 6000u32, 8000u32, 1000u32).into_val(&env),
                 sub_invokes: &[],
             },
         }
-    ]).configure_asset(&asset_symbol, &6000u32, &8000u32, &1000u32);
+    ]).list_asset(&asset_symbol, &6000u32, &8000u32, &1000u32);
 
     let deposit_amount = 1000_0000000i128;
 
@@ -267,12 +473,12 @@ fn test_deposit_collateral() {
             address: &admin,
             invoke: &MockAuthInvoke {
                 contract: &contract_id,
-                fn_name: "configure_asset",
+                fn_name: "list_asset",
                 args: (asset_symbol.clone(), 6000u32, 8000u32, 1000u32).into_val(&env),
                 sub_invokes: &[],
             },
         }
-    ]).configure_asset(&asset_symbol, &6000u32, &8000u32, &1000u32);
+    ]).list_asset(&asset_symbol, &6000u32, &8000u32, &1000u32);
 
     let collateral_amount = 1_0000000i128; // 1 stETH
 
@@ -288,4 +494,546 @@ fn test_deposit_collateral() {
             },
         }
     ]).deposit_collateral(&user, &asset_symbol, &collateral_amount);
-} 
\ No newline at end of file
+} 
+#[test]
+#[should_panic(expected = "supply cap exceeded")]
+fn test_supply_cap() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = create_lending_pool_contract(&env);
+    let client = LendingPoolClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let risk_admin = Address::generate(&env);
+    let price_oracle = create_mock_oracle_contract(&env);
+    let s_token_contract = create_mock_stoken_contract(&env);
+    let user = Address::generate(&env);
+
+    client.initialize(&admin, &s_token_contract, &price_oracle);
+
+    let asset_symbol = symbol_short!("stETH");
+    client.list_asset(&asset_symbol, &6000u32, &8000u32, &1000u32);
+    client.set_risk_admin(&risk_admin);
+
+    client.mock_auths(&[
+        MockAuth {
+            address: &risk_admin,
+            invoke: &MockAuthInvoke {
+                contract: &contract_id,
+                fn_name: "set_asset_caps",
+                args: (asset_symbol.clone(), 10_0000000i128, 0i128).into_val(&env),
+                sub_invokes: &[],
+            },
+        }
+    ]).set_asset_caps(&asset_symbol, &10_0000000i128, &0i128);
+
+    // Collateral counts towards the supply cap as well
+    client.deposit(&user, &asset_symbol, &6_0000000i128);
+    client.deposit_collateral(&user, &asset_symbol, &4_0000000i128);
+
+    let reserve = client.get_reserve_data(&asset_symbol);
+    assert_eq!(reserve.total_supplied, 6_0000000i128);
+    assert_eq!(reserve.total_collateral, 4_0000000i128);
+    assert_eq!(reserve.supply_cap, 10_0000000i128);
+    assert_eq!(reserve.borrow_cap, 0i128);
+
+    client.deposit(&user, &asset_symbol, &1i128);
+}
+
+#[test]
+#[should_panic(expected = "borrow cap exceeded")]
+fn test_borrow_cap() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = create_lending_pool_contract(&env);
+    let client = LendingPoolClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let price_oracle = create_mock_oracle_contract(&env);
+    let s_token_contract = create_mock_stoken_contract(&env);
+    let user = Address::generate(&env);
+
+    client.initialize(&admin, &s_token_contract, &price_oracle);
+
+    let collateral_asset = symbol_short!("XLM");
+    let borrow_asset = symbol_short!("USDC");
+    client.list_asset(&collateral_asset, &6000u32, &8000u32, &1000u32);
+    client.list_asset(&borrow_asset, &8000u32, &9000u32, &1000u32);
+    client.set_asset_caps(&borrow_asset, &0i128, &100_0000000i128);
+
+    client.deposit(&user, &borrow_asset, &1000_0000000i128);
+    client.deposit_collateral(&user, &collateral_asset, &10000_0000000i128);
+
+    client.borrow(&user, &borrow_asset, &100_0000000i128);
+    client.borrow(&user, &borrow_asset, &1_0000000i128);
+}
+
+#[test]
+fn test_emode_borrowing_power() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = create_lending_pool_contract(&env);
+    let client = LendingPoolClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let price_oracle = create_mock_oracle_contract(&env);
+    let s_token_contract = create_mock_stoken_contract(&env);
+    let supplier = Address::generate(&env);
+    let user = Address::generate(&env);
+
+    client.initialize(&admin, &s_token_contract, &price_oracle);
+
+    let steth = symbol_short!("stETH");
+    let weth = symbol_short!("WETH");
+    client.list_asset(&steth, &6000u32, &8000u32, &1000u32);
+    client.list_asset(&weth, &8000u32, &8500u32, &1000u32);
+
+    // ETH-correlated category priced off WETH
+    client.set_emode_category(&1u32, &EModeCategory {
+        ltv_ratio: 9000,
+        liquidation_threshold: 9300,
+        liquidation_bonus: 100,
+        price_source: EModePriceSource::Shared(weth.clone()),
+    });
+    client.set_asset_emode_category(&steth, &1u32);
+    client.set_asset_emode_category(&weth, &1u32);
+
+    client.deposit(&supplier, &weth, &10_0000000i128);
+    client.deposit_collateral(&user, &steth, &1_0000000i128);
+
+    // Outside efficiency mode: 60% of $1500 = $900 borrowing power
+    let account_data = client.get_user_account_data(&user);
+    assert_eq!(account_data.total_collateral_usd, 1500_0000000i128);
+    assert_eq!(account_data.borrow_limit_usd, 900_0000000i128);
+
+    client.set_user_emode(&user, &1u32);
+    assert_eq!(client.get_user_emode(&user), 1u32);
+
+    // In efficiency mode: 90% of $1600 (category price source) = $1440
+    let account_data = client.get_user_account_data(&user);
+    assert_eq!(account_data.total_collateral_usd, 1600_0000000i128);
+    assert_eq!(account_data.borrow_limit_usd, 1440_0000000i128);
+    assert_eq!(account_data.emode_category, 1u32);
+
+    client.borrow(&user, &weth, &8000000i128); // 0.8 WETH = $1280
+
+    let account_data = client.get_user_account_data(&user);
+    assert_eq!(account_data.total_debt_usd, 1280_0000000i128);
+    assert!(account_data.health_factor > 1_0000000i128);
+}
+
+#[test]
+#[should_panic(expected = "borrowed asset not in emode category")]
+fn test_set_user_emode_with_foreign_debt() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = create_lending_pool_contract(&env);
+    let client = LendingPoolClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let price_oracle = create_mock_oracle_contract(&env);
+    let s_token_contract = create_mock_stoken_contract(&env);
+    let user = Address::generate(&env);
+
+    client.initialize(&admin, &s_token_contract, &price_oracle);
+
+    let steth = symbol_short!("stETH");
+    let usdc = symbol_short!("USDC");
+    client.list_asset(&steth, &6000u32, &8000u32, &1000u32);
+    client.list_asset(&usdc, &8000u32, &9000u32, &1000u32);
+    client.set_emode_category(&1u32, &EModeCategory {
+        ltv_ratio: 9000,
+        liquidation_threshold: 9300,
+        liquidation_bonus: 100,
+        price_source: EModePriceSource::AssetPrice,
+    });
+    client.set_asset_emode_category(&steth, &1u32);
+
+    client.deposit(&user, &usdc, &1000_0000000i128);
+    client.deposit_collateral(&user, &steth, &1_0000000i128);
+    client.borrow(&user, &usdc, &100_0000000i128);
+
+    client.set_user_emode(&user, &1u32);
+}
+
+#[test]
+fn test_isolation_mode_debt_ceiling() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = create_lending_pool_contract(&env);
+    let client = LendingPoolClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let price_oracle = create_mock_oracle_contract(&env);
+    let s_token_contract = create_mock_stoken_contract(&env);
+    let supplier = Address::generate(&env);
+    let user = Address::generate(&env);
+
+    client.initialize(&admin, &s_token_contract, &price_oracle);
+
+    let steth = symbol_short!("stETH");
+    let usdc = symbol_short!("USDC");
+    client.list_asset(&steth, &6000u32, &8000u32, &1000u32);
+    client.list_asset(&usdc, &8000u32, &9000u32, &1000u32);
+
+    // Newly bridged stETH is isolated with a $1000 debt ceiling
+    client.set_asset_isolation(&steth, &1000_0000000i128, &false);
+    client.set_asset_isolation(&usdc, &0i128, &true);
+
+    client.deposit(&supplier, &usdc, &10000_0000000i128);
+    client.deposit_collateral(&user, &steth, &2_0000000i128);
+
+    let account_data = client.get_user_account_data(&user);
+    assert!(account_data.isolation_mode);
+
+    client.borrow(&user, &usdc, &600_0000000i128);
+    assert_eq!(client.get_isolated_debt(&steth), 600_0000000i128);
+
+    client.repay(&user, &usdc, &100_0000000i128);
+    assert_eq!(client.get_isolated_debt(&steth), 500_0000000i128);
+
+    // $1800 borrowing power, but the $1000 ceiling applies
+    let result = client.try_borrow(&user, &usdc, &600_0000000i128);
+    assert!(result.is_err());
+    assert_eq!(client.get_isolated_debt(&steth), 500_0000000i128);
+
+    // Repaying without a usable price still releases the ceiling
+    let oracle_client = MockOracleClient::new(&env, &price_oracle);
+    oracle_client.set_price(&usdc, &0i128);
+    client.repay(&user, &usdc, &100_0000000i128);
+    oracle_client.set_price(&usdc, &1_0000000i128);
+    assert_eq!(client.get_isolated_debt(&steth), 400_0000000i128);
+
+    // Debt borrowed in isolation keeps releasing the ceiling once stETH is no longer isolated
+    client.set_asset_isolation(&steth, &0i128, &false);
+    assert!(!client.get_user_account_data(&user).isolation_mode);
+    client.repay(&user, &usdc, &100_0000000i128);
+    assert_eq!(client.get_isolated_debt(&steth), 300_0000000i128);
+}
+
+#[test]
+#[should_panic(expected = "asset not borrowable in isolation mode")]
+fn test_isolation_mode_borrowable_assets() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = create_lending_pool_contract(&env);
+    let client = LendingPoolClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let price_oracle = create_mock_oracle_contract(&env);
+    let s_token_contract = create_mock_stoken_contract(&env);
+    let supplier = Address::generate(&env);
+    let user = Address::generate(&env);
+
+    client.initialize(&admin, &s_token_contract, &price_oracle);
+
+    let steth = symbol_short!("stETH");
+    let xlm = symbol_short!("XLM");
+    client.list_asset(&steth, &6000u32, &8000u32, &1000u32);
+    client.list_asset(&xlm, &6000u32, &8000u32, &1000u32);
+    client.set_asset_isolation(&steth, &1000_0000000i128, &false);
+
+    client.deposit(&supplier, &xlm, &10000_0000000i128);
+    client.deposit_collateral(&user, &steth, &1_0000000i128);
+
+    client.borrow(&user, &xlm, &100_0000000i128);
+}
+
+#[test]
+fn test_flash_loan() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (client, receiver, token_address) = setup_flash_loan(&env, true);
+    let asset_symbol = symbol_short!("USDC");
+    let initiator = Address::generate(&env);
+
+    client.flash_loan(&initiator, &receiver, &asset_symbol, &1000_0000000i128, &Bytes::new(&env));
+
+    // The initiator authorized the loan and the receiver was told who started it
+    assert_eq!(env.auths()[0].0, initiator);
+    assert_eq!(MockFlashLoanReceiverClient::new(&env, &receiver).get_initiator(), initiator);
+
+    // 0.09% fee on $1000 = $0.9, 10% of which goes to reserves
+    let token = token::Client::new(&env, &token_address);
+    assert_eq!(token.balance(&client.address), 10000_9000000i128);
+    assert_eq!(client.get_reserves(&asset_symbol), 900000i128);
+    let (total_supplied, _, _) = client.get_pool_info(&asset_symbol);
+    assert_eq!(total_supplied, 10000_8100000i128);
+}
+
+#[test]
+#[should_panic(expected = "flash loan not repaid")]
+fn test_flash_loan_not_repaid() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (client, receiver, _) = setup_flash_loan(&env, false);
+
+    client.flash_loan(&Address::generate(&env), &receiver, &symbol_short!("USDC"), &1000_0000000i128, &Bytes::new(&env));
+}
+
+#[test]
+fn test_confidence_interval_valuation() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = create_lending_pool_contract(&env);
+    let client = LendingPoolClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let price_oracle = create_mock_oracle_contract(&env);
+    let s_token_contract = create_mock_stoken_contract(&env);
+    let supplier = Address::generate(&env);
+    let user = Address::generate(&env);
+
+    client.initialize(&admin, &s_token_contract, &price_oracle);
+
+    let reth = symbol_short!("rETH");
+    let usdc = symbol_short!("USDC");
+    client.list_asset(&reth, &6000u32, &8000u32, &1000u32);
+    client.list_asset(&usdc, &8000u32, &8500u32, &1000u32);
+
+    client.deposit(&supplier, &reth, &10_0000000i128);
+    client.deposit(&supplier, &usdc, &1000_0000000i128);
+    client.deposit_collateral(&user, &reth, &1_0000000i128);
+
+    // Collateral is valued at $1500 - $30
+    let account_data = client.get_user_account_data(&user);
+    assert_eq!(account_data.total_collateral_usd, 1470_0000000i128);
+    assert_eq!(account_data.borrow_limit_usd, 882_0000000i128);
+
+    // Debt is valued at $1500 + $30
+    client.borrow(&user, &reth, &1000000i128);
+    client.borrow(&user, &usdc, &100_0000000i128);
+    let account_data = client.get_user_account_data(&user);
+    assert_eq!(account_data.total_debt_usd, 253_0000000i128);
+
+    // An interval wider than the price values the collateral at zero, never below
+    MockOracleClient::new(&env, &price_oracle).set_price(&reth, &20_0000000i128);
+    assert_eq!(client.get_user_account_data(&user).total_collateral_usd, 0i128);
+}
+
+#[test]
+fn test_liquidation() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = create_lending_pool_contract(&env);
+    let client = LendingPoolClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let price_oracle = create_mock_oracle_contract(&env);
+    let oracle_client = MockOracleClient::new(&env, &price_oracle);
+    let s_token_contract = create_mock_stoken_contract(&env);
+    let supplier = Address::generate(&env);
+    let user = Address::generate(&env);
+    let liquidator = Address::generate(&env);
+
+    client.initialize(&admin, &s_token_contract, &price_oracle);
+
+    let steth = symbol_short!("stETH");
+    let usdc = symbol_short!("USDC");
+    client.list_asset(&steth, &6000u32, &8000u32, &1000u32);
+    client.list_asset(&usdc, &8000u32, &8500u32, &1000u32);
+
+    client.deposit(&supplier, &usdc, &10000_0000000i128);
+    client.deposit_collateral(&user, &steth, &1_0000000i128);
+    client.borrow(&user, &usdc, &900_0000000i128);
+    let (usdc_token, steth_token) = set_liquidation_tokens(&env, &client, &admin, &liquidator);
+
+    assert!(client.try_liquidate(&liquidator, &user, &usdc, &steth, &100_0000000i128).is_err());
+
+    // stETH drops to $1000: 80% of $1000 no longer covers $900 of debt
+    oracle_client.set_price(&steth, &1000_0000000i128);
+    assert!(client.get_user_account_data(&user).health_factor < 1_0000000i128);
+
+    // Liquidators holding other collateral can seize isolated collateral
+    client.set_asset_isolation(&steth, &10000_0000000i128, &false);
+    client.deposit_collateral(&liquidator, &usdc, &100_0000000i128);
+
+    // At most half the debt is covered; $450 buys 0.45 stETH plus a 5% bonus
+    client.liquidate(&liquidator, &user, &usdc, &steth, &1000_0000000i128);
+
+    let user_data = client.get_user_account_data(&user);
+    assert_eq!(user_data.total_debt_usd, 450_0000000i128);
+    assert_eq!(user_data.total_collateral_usd, 527_5000000i128);
+
+    // The repaid debt is collected and the seized stETH paid out, not credited as collateral
+    assert_eq!(token::Client::new(&env, &usdc_token).balance(&liquidator), 550_0000000i128);
+    assert_eq!(token::Client::new(&env, &usdc_token).balance(&contract_id), 450_0000000i128);
+    assert_eq!(token::Client::new(&env, &steth_token).balance(&liquidator), 4725000i128);
+    assert_eq!(client.get_user_account_data(&liquidator).total_collateral_usd, 100_0000000i128);
+    assert_eq!(client.get_reserve_data(&steth).total_collateral, 5275000i128);
+}
+
+#[test]
+fn test_stale_price_policy() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = create_lending_pool_contract(&env);
+    let client = LendingPoolClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let price_oracle = create_mock_oracle_contract(&env);
+    let oracle_client = MockOracleClient::new(&env, &price_oracle);
+    let s_token_contract = create_mock_stoken_contract(&env);
+    let supplier = Address::generate(&env);
+    let user = Address::generate(&env);
+
+    client.initialize(&admin, &s_token_contract, &price_oracle);
+
+    let steth = symbol_short!("stETH");
+    let usdc = symbol_short!("USDC");
+    client.list_asset(&steth, &6000u32, &8000u32, &1000u32);
+    client.list_asset(&usdc, &8000u32, &8500u32, &1000u32);
+
+    client.deposit(&supplier, &usdc, &10000_0000000i128);
+    client.deposit_collateral(&user, &steth, &1_0000000i128);
+    client.borrow(&user, &usdc, &100_0000000i128);
+
+    // A stale collateral price blocks borrowing and withdrawals
+    oracle_client.set_stale(&steth, &true);
+    assert!(!client.get_user_account_data(&user).prices_fresh);
+    assert!(client.try_borrow(&user, &usdc, &10_0000000i128).is_err());
+    assert!(client.try_withdraw_collateral(&user, &steth, &1000000i128).is_err());
+
+    // Repaying and depositing keep working
+    client.repay(&user, &usdc, &50_0000000i128);
+    client.deposit_collateral(&user, &steth, &1_0000000i128);
+    client.deposit(&supplier, &usdc, &100_0000000i128);
+
+    oracle_client.set_stale(&steth, &false);
+    client.withdraw_collateral(&user, &steth, &1_0000000i128);
+    assert_eq!(client.get_user_account_data(&user).total_collateral_usd, 1500_0000000i128);
+    assert!(client.try_withdraw_collateral(&user, &steth, &1_0000000i128).is_err());
+}
+
+#[test]
+fn test_liquidation_grace_period() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = create_lending_pool_contract(&env);
+    let client = LendingPoolClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let price_oracle = create_mock_oracle_contract(&env);
+    let oracle_client = MockOracleClient::new(&env, &price_oracle);
+    let s_token_contract = create_mock_stoken_contract(&env);
+    let supplier = Address::generate(&env);
+    let user = Address::generate(&env);
+    let liquidator = Address::generate(&env);
+
+    client.initialize(&admin, &s_token_contract, &price_oracle);
+    client.set_liquidation_grace_period(&600u64);
+
+    let steth = symbol_short!("stETH");
+    let usdc = symbol_short!("USDC");
+    client.list_asset(&steth, &6000u32, &8000u32, &1000u32);
+    client.list_asset(&usdc, &8000u32, &8500u32, &1000u32);
+
+    client.deposit(&supplier, &usdc, &10000_0000000i128);
+    client.deposit_collateral(&user, &steth, &1_0000000i128);
+    client.borrow(&user, &usdc, &900_0000000i128);
+    set_liquidation_tokens(&env, &client, &admin, &liquidator);
+
+    // The stETH feed comes back at a lower price after an outage
+    env.ledger().with_mut(|li| li.timestamp = 10_000);
+    oracle_client.set_price(&steth, &1000_0000000i128);
+    oracle_client.set_resumed_at(&steth, &10_000u64);
+    assert_eq!(client.get_user_account_data(&user).prices_resumed_at, 10_000u64);
+
+    env.ledger().with_mut(|li| li.timestamp = 10_300);
+    assert!(client.try_liquidate(&liquidator, &user, &usdc, &steth, &100_0000000i128).is_err());
+
+    // The user can still react during the grace period
+    client.repay(&user, &usdc, &10_0000000i128);
+    client.deposit_collateral(&user, &steth, &1000000i128);
+
+    env.ledger().with_mut(|li| li.timestamp = 10_600);
+    client.liquidate(&liquidator, &user, &usdc, &steth, &100_0000000i128);
+    assert_eq!(client.get_user_account_data(&user).total_debt_usd, 790_0000000i128);
+}
+
+#[test]
+fn test_price_sources() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = create_lending_pool_contract(&env);
+    let client = LendingPoolClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let price_oracle = create_mock_oracle_contract(&env);
+    let sep40_oracle = env.register_contract(None, MockSep40Oracle {});
+    let sep40_client = MockSep40OracleClient::new(&env, &sep40_oracle);
+    let s_token_contract = create_mock_stoken_contract(&env);
+    let supplier = Address::generate(&env);
+    let user = Address::generate(&env);
+    let liquidator = Address::generate(&env);
+
+    client.initialize(&admin, &s_token_contract, &price_oracle);
+
+    let steth = symbol_short!("stETH");
+    let usdc = symbol_short!("USDC");
+    client.list_asset(&steth, &6000u32, &8000u32, &1000u32);
+    client.list_asset(&usdc, &8000u32, &8500u32, &1000u32);
+    assert_eq!(
+        client.get_price_source(&steth),
+        PriceSourceConfig::Paralyx(price_oracle.clone(), steth.clone(), 7u32)
+    );
+
+    // stETH from a SEP-40 feed with 14 decimals, USDC pegged at $1 with 6 decimals
+    env.ledger().with_mut(|li| li.timestamp = 10_000);
+    let steth_key = OracleAsset::Other(symbol_short!("STETH"));
+    sep40_client.set_lastprice(&steth_key, &1500_00000000000000i128, &10_000u64);
+    client.set_price_source(&steth, &PriceSourceConfig::Sep40(sep40_oracle.clone(), steth_key.clone(), 14u32, 600u64));
+    assert!(client.try_set_price_source(&steth, &PriceSourceConfig::Sep40(sep40_oracle.clone(), steth_key.clone(), 14u32, 0u64)).is_err());
+    client.set_price_source(&usdc, &PriceSourceConfig::Constant(1_000000i128, 6u32));
+    assert!(client.try_set_price_source(&usdc, &PriceSourceConfig::Constant(0i128, 6u32)).is_err());
+
+    client.deposit(&supplier, &usdc, &10000_0000000i128);
+    client.deposit_collateral(&user, &steth, &1_0000000i128);
+    client.borrow(&user, &usdc, &900_0000000i128);
+    set_liquidation_tokens(&env, &client, &admin, &liquidator);
+
+    let account_data = client.get_user_account_data(&user);
+    assert_eq!(account_data.total_collateral_usd, 1500_0000000i128);
+    assert_eq!(account_data.total_debt_usd, 900_0000000i128);
+
+    // A max age without a limit does not overflow
+    client.set_price_source(&steth, &PriceSourceConfig::Sep40(sep40_oracle.clone(), steth_key.clone(), 14u32, u64::MAX));
+    assert!(client.get_user_account_data(&user).prices_fresh);
+    client.set_price_source(&steth, &PriceSourceConfig::Sep40(sep40_oracle.clone(), steth_key.clone(), 14u32, 600u64));
+
+    // An old SEP-40 price is stale
+    env.ledger().with_mut(|li| li.timestamp = 10_000 + 601);
+    assert!(!client.get_user_account_data(&user).prices_fresh);
+    assert!(client.try_liquidate(&liquidator, &user, &usdc, &steth, &100_0000000i128).is_err());
+
+    // The feed comes back at a lower price, liquidations wait out the grace period
+    client.set_liquidation_grace_period(&300u64);
+    env.ledger().with_mut(|li| li.timestamp = 10_700);
+    sep40_client.set_lastprice(&steth_key, &1000_00000000000000i128, &10_700u64);
+    assert!(client.get_user_account_data(&user).prices_fresh);
+    assert_eq!(client.get_user_account_data(&user).prices_resumed_at, 10_700u64);
+    assert!(client.try_liquidate(&liquidator, &user, &usdc, &steth, &1000_0000000i128).is_err());
+
+    // Updates within the max age are not outages
+    env.ledger().with_mut(|li| li.timestamp = 11_000);
+    sep40_client.set_lastprice(&steth_key, &1000_00000000000000i128, &11_000u64);
+    assert_eq!(client.get_user_account_data(&user).prices_resumed_at, 10_700u64);
+    client.liquidate(&liquidator, &user, &usdc, &steth, &1000_0000000i128);
+
+    let user_data = client.get_user_account_data(&user);
+    assert_eq!(user_data.total_debt_usd, 450_0000000i128);
+    assert_eq!(user_data.total_collateral_usd, 527_5000000i128);
+}
@@ -1,13 +1,32 @@
 #![no_std]
 
 use soroban_sdk::{
-    contract, contractimpl, contracttype, vec, Address, Env, Symbol, Vec,
-    symbol_short
+    contract, contractimpl, contracttype, vec, xdr::ToXdr, Address, BytesN, Env, String,
+    Symbol, Vec, symbol_short
+};
+use paralyx_math::{
+    fixed_div_floor, fixed_mul_ceil, fixed_mul_floor, mul_div_floor, to_bps_floor, BPS_DENOMINATOR,
 };
 
 pub(crate) const DAY_IN_LEDGERS: u32 = 17280;
 pub(crate) const INSTANCE_BUMP_AMOUNT: u32 = 7 * DAY_IN_LEDGERS;
 pub(crate) const INSTANCE_LIFETIME_THRESHOLD: u32 = INSTANCE_BUMP_AMOUNT - DAY_IN_LEDGERS;
+pub(crate) const HISTORY_BUMP_AMOUNT: u32 = 30 * DAY_IN_LEDGERS;
+pub(crate) const HISTORY_LIFETIME_THRESHOLD: u32 = HISTORY_BUMP_AMOUNT - DAY_IN_LEDGERS;
+
+pub(crate) const DEFAULT_MAX_AGE: u64 = 3600; // 1 hour
+pub(crate) const DEFAULT_QUORUM: u32 = 2; // A single feeder never publishes on its own
+pub(crate) const DEFAULT_HISTORY_SIZE: u32 = 48;
+pub(crate) const MAX_HISTORY_SIZE: u32 = 500;
+pub(crate) const DECIMALS: u32 = 7;
+pub(crate) const RESOLUTION: u32 = 300; // Expected update cadence in seconds
+pub(crate) const SECONDS_PER_YEAR: u64 = 31_536_000;
+pub(crate) const MAX_PAIR_DECIMALS: u32 = 18;
+pub(crate) const MAX_ROUTE_HOPS: u32 = 2; // Nested routes resolved for a pair price
+pub(crate) const USD: Symbol = symbol_short!("USD"); // Quote of the asset prices
+pub(crate) const SEP40_OUTAGE_RECORDS: u32 = 24; // External history scanned for the latest outage
+
+pub use sep40::{Asset, Sep40, Sep40Client};
 
 #[derive(Clone)]
 #[contracttype]
@@ -15,15 +34,226 @@ pub enum DataKey {
     Admin,
     Price(Symbol),
     LastUpdated(Symbol),
+    Feeders,                      // Whitelisted price feeders
+    Quorum,                       // Fresh submissions required to publish a median
+    Round(Symbol),                // Current submission round for an asset
+    Submission(Symbol, Address),  // Latest submission of a feeder for an asset
+    MinUpdateInterval(Symbol),    // Ledgers a feeder must wait between submissions for an asset
+    FeederStats(Address),         // Submission accounting of a feeder
+    Pair(Symbol, Symbol),         // Registration of a (base, quote) pair
+    PairPrice(Symbol, Symbol),    // Stored price of a pair in its own decimals
+    PairRoute(Symbol, Symbol),    // Intermediate asset a pair price is routed through
+    PairList,                     // All registered pairs
+    AlertConfig(Symbol),          // Monitoring thresholds of an asset
+    ActiveFeeders(Symbol),        // Feeders with a fresh submission for an asset at the last check
+    Publishers,                   // Ed25519 keys allowed to sign price updates
+    PublisherNonce(BytesN<32>),   // Last nonce used by a publisher
+    Observation(Symbol, u32),     // Price observation slot in an asset's ring buffer (persistent)
+    ObservationState(Symbol),     // Ring buffer position for an asset's observations (persistent)
+    MaxAge(Symbol),               // Heartbeat after which an asset's price is stale
+    MaxDeviation(Symbol),         // Max deviation between consecutive updates in basis points
+    PendingPrice(Symbol),         // Update held back by the deviation circuit breaker
+    AssetList,                    // All registered assets
+    AssetInfo(Symbol),            // Registration details of an asset
+    StellarAsset(Address),        // Symbol of a Stellar asset contract (SEP-40)
+    AssetAddress(Symbol),         // Stellar asset contract of a symbol (SEP-40)
+    Derived(Symbol),              // Derivation of an asset price from a base asset
+    DerivedAssets(Symbol),        // Assets whose price is derived from a base asset
+    Ratio(Symbol),                // Exchange rate of a derived asset to its base
+    RatioSnapshot(Symbol),        // Trusted ratio a derived asset's growth is bounded from
+    Sources(Symbol),              // Ordered price sources of an asset
+    MaxSpread(Symbol),            // Max spread between fresh sources in basis points
+    MaxConfidence(Symbol),        // Max confidence interval relative to price in basis points
+    ResumedAt(Symbol),            // First update after the asset's price went stale
 }
 
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct PriceData {
     pub price: i128,      // Price with 7 decimals
+    pub conf: i128,       // Confidence interval around the price with 7 decimals
+    pub timestamp: u64,   // Last update timestamp
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PriceSubmission {
+    pub feeder: Address,
+    pub price: i128,      // Price with 7 decimals
+    pub timestamp: u64,   // Submission timestamp
+    pub ledger: u32,      // Submission ledger sequence
+    pub round: u32,       // Round the submission belongs to
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FeederStats {
+    pub submissions: u32,    // Prices submitted across all assets
+    pub last_ledger: u32,    // Ledger of the latest submission (0 if never)
+    pub last_timestamp: u64, // Timestamp of the latest submission (0 if never)
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SignedPriceUpdate {
+    pub asset: Symbol,
+    pub price: i128,      // Price with 7 decimals
+    pub conf: i128,       // Confidence interval with 7 decimals
+    pub timestamp: u64,   // Time the publisher observed the price
+    pub nonce: u64,       // Strictly increasing per publisher
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Observation {
+    pub price: i128,      // Price with 7 decimals
+    pub conf: i128,       // Confidence interval with 7 decimals
+    pub timestamp: u64,   // Observation timestamp
+    pub cumulative: i128, // Sum of price * seconds up to the timestamp
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ObservationState {
+    pub next: u32,        // Slot the next observation is written to
+    pub count: u32,       // Number of stored observations
+    pub capacity: u32,    // Ring buffer size
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct UpdateInfo {
+    pub last_updated: u64, // Last update timestamp
+    pub max_age: u64,      // Configured heartbeat in seconds
+    pub is_fresh: bool,    // Whether the price is within its heartbeat
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PendingPrice {
+    pub price: i128,         // Proposed price with 7 decimals
+    pub conf: i128,          // Proposed confidence interval
+    pub timestamp: u64,      // Proposal timestamp
+    pub proposers: Vec<Address>, // Admin or every feeder whose submission made up the update
+    pub deviation_bps: u32,  // Deviation from the current price in basis points
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AssetInfo {
+    pub decimals: u32,        // Decimals of the asset's token amounts
+    pub description: String,  // Human readable description
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DerivedAsset {
+    pub base: Symbol,                // Asset the price is derived from (e.g. ETH)
+    pub max_annual_growth_bps: u32,  // Max yearly ratio growth from the snapshot in basis points
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RatioData {
+    pub ratio: i128,      // Base units per derived unit with 7 decimals
     pub timestamp: u64,   // Last update timestamp
 }
 
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RatioSnapshot {
+    pub ratio: i128,      // Trusted ratio with 7 decimals
+    pub timestamp: u64,   // Time the ratio was observed
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PairInfo {
+    pub base: Symbol,
+    pub quote: Symbol,     // Asset the base is priced in
+    pub decimals: u32,     // Decimals of the pair price
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PairPrice {
+    pub price: i128,       // Price of one base in quote
+    pub decimals: u32,     // Decimals of the price
+    pub timestamp: u64,    // Oldest timestamp among the prices used
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AlertConfig {
+    pub soft_deviation_bps: u32,     // Update deviation reported as an alert (0 = disabled)
+    pub staleness_warning_secs: u64, // Reads this close to staleness are reported (0 = disabled)
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DeviationAlert {
+    pub asset: Symbol,
+    pub old_price: i128,
+    pub new_price: i128,
+    pub deviation_bps: u32,
+    pub threshold_bps: u32,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct QuorumAlert {
+    pub asset: Symbol,
+    pub old_active_feeders: u32, // Feeders with a fresh submission at the previous check
+    pub active_feeders: u32,     // Feeders with a fresh submission now
+    pub quorum: u32,             // Submissions required to publish a price
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StalenessAlert {
+    pub asset: Symbol,
+    pub last_updated: u64,   // Timestamp of the price read
+    pub read_at: u64,        // Timestamp of the read
+    pub max_age: u64,        // Heartbeat after which the price is stale
+    pub warning_secs: u64,   // Configured warning window
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RatioInfo {
+    pub reported: i128,    // Latest reported ratio
+    pub capped: i128,      // Ratio used for pricing
+    pub max_ratio: i128,   // Current cap from the snapshot
+    pub is_capped: bool,   // Whether the reported ratio exceeds the cap
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum PriceSource {
+    Local,                  // Prices published by feeders of this oracle
+    Sep40(Address, Asset),  // External SEP-40 oracle and its asset id
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SourcedPrice {
+    pub price: i128,          // Price in USD with 7 decimals
+    pub conf: i128,           // Confidence interval with 7 decimals
+    pub timestamp: u64,       // Timestamp reported by the source
+    pub source: PriceSource,  // Source the price was read from
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PriceStatus {
+    pub price: i128,          // Price in USD with 7 decimals (0 if never published)
+    pub conf: i128,           // Confidence interval with 7 decimals
+    pub timestamp: u64,       // Timestamp reported by the source
+    pub is_fresh: bool,       // Whether the price passes freshness and sanity checks
+    pub source: PriceSource,  // Source the price was read from
+    pub resumed_at: u64,      // When the feed last recovered from staleness (0 if never)
+}
+
 #[contract]
 pub struct PriceOracle;
 
@@ -36,11 +266,32 @@ impl PriceOracle {
         }
 
         env.storage().instance().set(&DataKey::Admin, &admin);
+    }
+
+    /// Register an asset so prices can be published for it (admin only)
+    pub fn register_asset(env: Env, asset: Symbol, decimals: u32, description: String) {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+
+        if env.storage().instance().has(&DataKey::AssetInfo(asset.clone())) {
+            panic!("asset already registered");
+        }
+
+        let info = AssetInfo { decimals, description };
+        env.storage().instance().set(&DataKey::AssetInfo(asset.clone()), &info);
+
+        let mut assets = Self::get_assets(env.clone());
+        assets.push_back(asset.clone());
+        env.storage().instance().set(&DataKey::AssetList, &assets);
+
+        env.events().publish((symbol_short!("asset_reg"), asset), decimals);
+    }
 
-        // Set initial mock prices (for testing)
-        Self::set_price_internal(env.clone(), symbol_short!("stETH"), 1500_0000000i128); // $1500
-        Self::set_price_internal(env.clone(), symbol_short!("XLM"), 12_0000000i128);     // $0.12  
-        Self::set_price_internal(env.clone(), symbol_short!("USDC"), 1_0000000i128);    // $1.00
+    /// Get the registration details of an asset
+    pub fn get_asset_info(env: Env, asset: Symbol) -> AssetInfo {
+        env.storage().instance()
+            .get(&DataKey::AssetInfo(asset))
+            .unwrap_or_else(|| panic!("asset not registered"))
     }
 
     /// Set price for an asset (admin only)
@@ -48,7 +299,17 @@ impl PriceOracle {
         let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
         admin.require_auth();
 
-        Self::set_price_internal(env, asset, price);
+        let timestamp = env.ledger().timestamp();
+        Self::set_price_internal(env.clone(), asset, price, 0, timestamp, vec![&env, admin]);
+    }
+
+    /// Set price and confidence interval for an asset (admin only)
+    pub fn set_price_with_confidence(env: Env, asset: Symbol, price: i128, conf: i128) {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+
+        let timestamp = env.ledger().timestamp();
+        Self::set_price_internal(env.clone(), asset, price, conf, timestamp, vec![&env, admin]);
     }
 
     /// Set multiple prices at once (admin only)  
@@ -61,96 +322,1410 @@ impl PriceOracle {
         }
 
         for i in 0..assets.len() {
-            Self::set_price_internal(env.clone(), assets.get(i).unwrap(), prices.get(i).unwrap());
+            Self::set_price_internal(env.clone(), assets.get(i).unwrap(), prices.get(i).unwrap(), 0, env.ledger().timestamp(), vec![&env, admin.clone()]);
         }
     }
 
-    /// Get price for an asset in USD (with 7 decimals)
-    pub fn get_price(env: Env, asset: Symbol) -> i128 {
-        let price_data: PriceData = env.storage()
-            .instance()
-            .get(&DataKey::Price(asset))
-            .unwrap_or_else(|| panic!("price not found for asset"));
+    /// Add a whitelisted price feeder (admin only)
+    pub fn add_feeder(env: Env, feeder: Address) {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
 
-        // Check if price is fresh (within 1 hour = 3600 seconds)
-        let current_timestamp = env.ledger().timestamp();
-        if current_timestamp > price_data.timestamp + 3600 {
-            panic!("price data is stale");
+        let mut feeders = Self::get_feeders(env.clone());
+        if feeders.contains(&feeder) {
+            panic!("feeder already added");
         }
+        feeders.push_back(feeder.clone());
+        env.storage().instance().set(&DataKey::Feeders, &feeders);
 
-        price_data.price
+        env.events().publish((symbol_short!("feed_add"),), feeder);
     }
 
-    /// Get price with freshness check disabled (for testing)
-    pub fn get_price_unchecked(env: Env, asset: Symbol) -> i128 {
-        let price_data: PriceData = env.storage()
-            .instance()
-            .get(&DataKey::Price(asset))
-            .unwrap_or_else(|| panic!("price not found for asset"));
+    /// Remove a whitelisted price feeder (admin only)
+    pub fn remove_feeder(env: Env, feeder: Address) {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
 
-        price_data.price
+        let mut feeders = Self::get_feeders(env.clone());
+        let index = feeders.first_index_of(&feeder)
+            .unwrap_or_else(|| panic!("feeder not found"));
+        feeders.remove(index);
+
+        // The quorum must stay reachable, lower it before removing the feeder
+        if Self::get_quorum(env.clone()) > feeders.len() {
+            panic!("quorum exceeds feeders");
+        }
+        env.storage().instance().set(&DataKey::Feeders, &feeders);
+
+        // The feeder's fresh submissions no longer count toward any asset's quorum
+        for asset in Self::get_assets(env.clone()).iter() {
+            Self::check_quorum(&env, &asset);
+        }
+
+        env.events().publish((symbol_short!("feed_rm"),), feeder);
     }
 
-    /// Get multiple prices at once
-    pub fn get_prices(env: Env, assets: Vec<Symbol>) -> Vec<i128> {
-        let mut prices = vec![&env];
+    /// Set the number of fresh submissions required to publish a price (admin only)
+    pub fn set_quorum(env: Env, quorum: u32) {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
 
-        for i in 0..assets.len() {
-            let price = Self::get_price(env.clone(), assets.get(i).unwrap());
-            prices.push_back(price);
+        if quorum < DEFAULT_QUORUM {
+            panic!("quorum too low");
+        }
+        if quorum > Self::get_feeders(env.clone()).len() {
+            panic!("quorum exceeds feeders");
         }
 
-        prices
+        env.storage().instance().set(&DataKey::Quorum, &quorum);
+        env.events().publish((symbol_short!("quorum"),), quorum);
     }
 
-    /// Convert amount from one asset to USD value
-    pub fn convert_to_usd(env: Env, asset: Symbol, amount: i128) -> i128 {
-        let price = Self::get_price(env, asset);
-        amount * price / 10_000_000i128
+    /// Submit a price as a whitelisted feeder. Once a quorum of fresh
+    /// submissions exists for the current round, their median is published.
+    pub fn submit_price(env: Env, feeder: Address, asset: Symbol, price: i128) {
+        feeder.require_auth();
+
+        if !Self::get_feeders(env.clone()).contains(&feeder) {
+            panic!("not a feeder");
+        }
+
+        Self::require_registered(&env, &asset);
+
+        if price <= 0 {
+            panic!("price must be positive");
+        }
+
+        let min_interval = Self::get_min_update_interval(env.clone(), asset.clone());
+        if let Some(previous) = env.storage().instance()
+            .get::<DataKey, PriceSubmission>(&DataKey::Submission(asset.clone(), feeder.clone()))
+        {
+            if env.ledger().sequence() < previous.ledger.saturating_add(min_interval) {
+                panic!("update too frequent");
+            }
+        }
+
+        let round = Self::get_round(env.clone(), asset.clone());
+        let submission = PriceSubmission {
+            feeder: feeder.clone(),
+            price,
+            timestamp: env.ledger().timestamp(),
+            ledger: env.ledger().sequence(),
+            round,
+        };
+        env.storage().instance().set(&DataKey::Submission(asset.clone(), feeder.clone()), &submission);
+
+        let mut stats = Self::get_feeder_stats(env.clone(), feeder.clone());
+        stats.submissions += 1;
+        stats.last_ledger = submission.ledger;
+        stats.last_timestamp = submission.timestamp;
+        env.storage().instance().set(&DataKey::FeederStats(feeder.clone()), &stats);
+
+        env.events().publish((symbol_short!("price_sub"), asset.clone(), feeder.clone()), price);
+
+        // Collect fresh submissions for the current round
+        let max_age = Self::get_max_age(env.clone(), asset.clone());
+        let mut prices: Vec<i128> = vec![&env];
+        let mut contributors: Vec<Address> = vec![&env];
+        for submission in Self::get_submissions(env.clone(), asset.clone()).iter() {
+            if submission.round == round
+                && env.ledger().timestamp() <= submission.timestamp + max_age
+            {
+                prices.push_back(submission.price);
+                contributors.push_back(submission.feeder);
+            }
+        }
+
+        Self::check_quorum(&env, &asset);
+
+        if prices.len() >= Self::get_quorum(env.clone()) {
+            // Feeder disagreement becomes the confidence interval
+            let mut low = i128::MAX;
+            let mut high = 0i128;
+            for price in prices.iter() {
+                low = low.min(price);
+                high = high.max(price);
+            }
+            let median = Self::median(prices);
+            let conf = (high - low) / 2;
+
+            // Feeders too far apart to give a usable interval keep the round open
+            if conf < median {
+                Self::set_price_internal(env.clone(), asset.clone(), median, conf, env.ledger().timestamp(), contributors);
+                env.storage().instance().set(&DataKey::Round(asset), &(round + 1));
+            }
+        }
     }
 
-    /// Convert USD value to asset amount
-    pub fn convert_from_usd(env: Env, asset: Symbol, usd_amount: i128) -> i128 {
-        let price = Self::get_price(env, asset);
-        usd_amount * 10_000_000i128 / price
+    /// Get the latest submission of every whitelisted feeder for an asset
+    pub fn get_submissions(env: Env, asset: Symbol) -> Vec<PriceSubmission> {
+        let mut submissions = vec![&env];
+        for feeder in Self::get_feeders(env.clone()).iter() {
+            if let Some(submission) = env.storage().instance()
+                .get::<DataKey, PriceSubmission>(&DataKey::Submission(asset.clone(), feeder))
+            {
+                submissions.push_back(submission);
+            }
+        }
+        submissions
     }
 
-    /// Get the timestamp of last price update
-    pub fn get_last_updated(env: Env, asset: Symbol) -> u64 {
-        let price_data: PriceData = env.storage()
-            .instance()
-            .get(&DataKey::Price(asset))
-            .unwrap_or_else(|| panic!("price not found for asset"));
+    /// Get the latest submission of a feeder for an asset
+    pub fn get_submission(env: Env, asset: Symbol, feeder: Address) -> PriceSubmission {
+        env.storage().instance()
+            .get(&DataKey::Submission(asset, feeder))
+            .unwrap_or_else(|| panic!("submission not found"))
+    }
+
+    /// Set the ledgers a feeder must wait between submissions for an asset (admin only, 0 = disabled)
+    pub fn set_min_update_interval(env: Env, asset: Symbol, ledgers: u32) {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
 
-        price_data.timestamp
+        env.storage().instance().set(&DataKey::MinUpdateInterval(asset.clone()), &ledgers);
+        env.events().publish((symbol_short!("min_intvl"), asset), ledgers);
     }
 
-    /// Check if price data is fresh (within 1 hour)
-    pub fn is_price_fresh(env: Env, asset: Symbol) -> bool {
-        if let Some(price_data) = env.storage().instance().get::<DataKey, PriceData>(&DataKey::Price(asset)) {
-            let current_timestamp = env.ledger().timestamp();
-            current_timestamp <= price_data.timestamp + 3600
-        } else {
-            false
+    /// Get the ledgers a feeder must wait between submissions for an asset
+    pub fn get_min_update_interval(env: Env, asset: Symbol) -> u32 {
+        env.storage().instance()
+            .get(&DataKey::MinUpdateInterval(asset))
+            .unwrap_or(0u32)
+    }
+
+    /// Get how many prices a feeder submitted and when it was last active
+    pub fn get_feeder_stats(env: Env, feeder: Address) -> FeederStats {
+        env.storage().instance()
+            .get(&DataKey::FeederStats(feeder))
+            .unwrap_or(FeederStats { submissions: 0, last_ledger: 0, last_timestamp: 0 })
+    }
+
+    /// Get whitelisted price feeders
+    pub fn get_feeders(env: Env) -> Vec<Address> {
+        env.storage().instance().get(&DataKey::Feeders).unwrap_or(vec![&env])
+    }
+
+    /// Get the number of fresh submissions required to publish a price
+    pub fn get_quorum(env: Env) -> u32 {
+        env.storage().instance().get(&DataKey::Quorum).unwrap_or(DEFAULT_QUORUM)
+    }
+
+    /// Get the current submission round for an asset
+    pub fn get_round(env: Env, asset: Symbol) -> u32 {
+        env.storage().instance().get(&DataKey::Round(asset)).unwrap_or(0u32)
+    }
+
+    /// Allow an ed25519 key to sign price updates (admin only)
+    pub fn add_publisher(env: Env, publisher: BytesN<32>) {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+
+        let mut publishers = Self::get_publishers(env.clone());
+        if publishers.contains(&publisher) {
+            panic!("publisher already added");
         }
+        publishers.push_back(publisher.clone());
+        env.storage().instance().set(&DataKey::Publishers, &publishers);
+
+        env.events().publish((symbol_short!("pub_add"),), publisher);
     }
 
-    // Internal helper function
-    fn set_price_internal(env: Env, asset: Symbol, price: i128) {
-        if price <= 0 {
-            panic!("price must be positive");
+    /// Revoke an ed25519 publisher key (admin only)
+    pub fn remove_publisher(env: Env, publisher: BytesN<32>) {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+
+        let mut publishers = Self::get_publishers(env.clone());
+        let index = publishers
+            .first_index_of(&publisher)
+            .unwrap_or_else(|| panic!("not a publisher"));
+        publishers.remove(index);
+        env.storage().instance().set(&DataKey::Publishers, &publishers);
+
+        env.events().publish((symbol_short!("pub_rm"),), publisher);
+    }
+
+    /// Submit a batch of price updates signed by a publisher. Anyone can relay
+    /// the batch; the signature covers the XDR encoding of `updates`.
+    pub fn submit_signed_prices(
+        env: Env,
+        publisher: BytesN<32>,
+        updates: Vec<SignedPriceUpdate>,
+        signature: BytesN<64>,
+    ) {
+        if !Self::get_publishers(env.clone()).contains(&publisher) {
+            panic!("not a publisher");
         }
 
+        // Bind the batch to this deployment and network so it cannot be replayed elsewhere
+        let message = (env.current_contract_address(), env.ledger().network_id(), updates.clone()).to_xdr(&env);
+        env.crypto().ed25519_verify(&publisher, &message, &signature);
+
         let current_timestamp = env.ledger().timestamp();
-        let price_data = PriceData {
-            price,
-            timestamp: current_timestamp,
-        };
+        let mut nonce = Self::get_publisher_nonce(env.clone(), publisher.clone());
 
-        env.storage().instance().set(&DataKey::Price(asset.clone()), &price_data);
+        for update in updates.iter() {
+            if update.nonce <= nonce {
+                panic!("nonce already used");
+            }
+            nonce = update.nonce;
+
+            if update.timestamp > current_timestamp {
+                panic!("timestamp in the future");
+            }
+            if current_timestamp - update.timestamp > Self::get_max_age(env.clone(), update.asset.clone()) {
+                panic!("price update too old");
+            }
+            if let Some(price_data) = env.storage().instance().get::<DataKey, PriceData>(&DataKey::Price(update.asset.clone())) {
+                if update.timestamp < price_data.timestamp {
+                    panic!("price update older than current price");
+                }
+            }
+
+            Self::set_price_internal(env.clone(), update.asset, update.price, update.conf, update.timestamp, vec![&env, env.current_contract_address()]);
+        }
+
+        env.storage().instance().set(&DataKey::PublisherNonce(publisher), &nonce);
+    }
+
+    /// Get all ed25519 publisher keys
+    pub fn get_publishers(env: Env) -> Vec<BytesN<32>> {
+        env.storage().instance().get(&DataKey::Publishers).unwrap_or(vec![&env])
+    }
+
+    /// Get the last nonce used by a publisher
+    pub fn get_publisher_nonce(env: Env, publisher: BytesN<32>) -> u64 {
+        env.storage().instance().get(&DataKey::PublisherNonce(publisher)).unwrap_or(0)
+    }
+
+    /// Set the heartbeat after which an asset's price is stale (admin only)
+    pub fn set_max_age(env: Env, asset: Symbol, max_age: u64) {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+
+        if max_age == 0 {
+            panic!("max age must be positive");
+        }
+
+        env.storage().instance().set(&DataKey::MaxAge(asset.clone()), &max_age);
+        env.events().publish((symbol_short!("max_age"), asset), max_age);
+    }
+
+    /// Get the heartbeat after which an asset's price is stale
+    pub fn get_max_age(env: Env, asset: Symbol) -> u64 {
+        env.storage().instance()
+            .get(&DataKey::MaxAge(asset))
+            .unwrap_or(DEFAULT_MAX_AGE)
+    }
+
+    /// Set the thresholds at which alert events are emitted for an asset (admin only, 0 = disabled)
+    pub fn set_alert_config(env: Env, asset: Symbol, soft_deviation_bps: u32, staleness_warning_secs: u64) {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+
+        let config = AlertConfig { soft_deviation_bps, staleness_warning_secs };
+        env.storage().instance().set(&DataKey::AlertConfig(asset.clone()), &config);
+        env.events().publish((symbol_short!("alert_cfg"), asset), config);
+    }
+
+    /// Get the thresholds at which alert events are emitted for an asset
+    pub fn get_alert_config(env: Env, asset: Symbol) -> AlertConfig {
+        env.storage().instance()
+            .get(&DataKey::AlertConfig(asset))
+            .unwrap_or(AlertConfig { soft_deviation_bps: 0, staleness_warning_secs: 0 })
+    }
+
+    /// Set the max deviation between consecutive updates in basis points (admin only, 0 = disabled)
+    pub fn set_max_deviation(env: Env, asset: Symbol, max_deviation_bps: u32) {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+
+        env.storage().instance().set(&DataKey::MaxDeviation(asset.clone()), &max_deviation_bps);
+        env.events().publish((symbol_short!("max_dev"), asset), max_deviation_bps);
+    }
+
+    /// Get the max deviation between consecutive updates in basis points
+    pub fn get_max_deviation(env: Env, asset: Symbol) -> u32 {
+        env.storage().instance()
+            .get(&DataKey::MaxDeviation(asset))
+            .unwrap_or(0u32)
+    }
+
+    /// Confirm a pending price as a feeder that did not propose it. The feeder's
+    /// own fresh submission must be within the max deviation of the pending price.
+    pub fn confirm_pending_price(env: Env, feeder: Address, asset: Symbol) {
+        feeder.require_auth();
+
+        if !Self::get_feeders(env.clone()).contains(&feeder) {
+            panic!("not a feeder");
+        }
+
+        let pending = Self::get_pending_price(env.clone(), asset.clone());
+        if pending.proposers.contains(&feeder) {
+            panic!("proposer cannot confirm");
+        }
+        Self::require_pending_fresh(&env, &asset, &pending);
+
+        let submission: PriceSubmission = env.storage().instance()
+            .get(&DataKey::Submission(asset.clone(), feeder.clone()))
+            .unwrap_or_else(|| panic!("no submission to confirm with"));
+        if env.ledger().timestamp() > submission.timestamp + Self::get_max_age(env.clone(), asset.clone()) {
+            panic!("submission too old to confirm");
+        }
+        let max_deviation = Self::get_max_deviation(env.clone(), asset.clone()) as i128;
+        if max_deviation > 0 && to_bps_floor((submission.price - pending.price).abs(), pending.price) > max_deviation {
+            panic!("submission disagrees with pending price");
+        }
+
+        env.storage().instance().remove(&DataKey::PendingPrice(asset.clone()));
+        Self::publish_price(&env, &asset, pending.price, pending.conf, pending.timestamp);
+
+        env.events().publish((symbol_short!("pend_ok"), asset, feeder), pending.price);
+    }
 
-        env.events().publish((symbol_short!("price_upd"), asset), price);
+    /// Accept or discard a pending price (admin only)
+    pub fn override_pending_price(env: Env, asset: Symbol, accept: bool) {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+
+        let pending = Self::get_pending_price(env.clone(), asset.clone());
+        if accept {
+            Self::require_pending_fresh(&env, &asset, &pending);
+        }
+        env.storage().instance().remove(&DataKey::PendingPrice(asset.clone()));
+
+        if accept {
+            Self::publish_price(&env, &asset, pending.price, pending.conf, pending.timestamp);
+        }
+
+        env.events().publish((symbol_short!("pend_ovr"), asset), (pending.price, accept));
+    }
+
+    /// Get the update held back by the deviation circuit breaker
+    pub fn get_pending_price(env: Env, asset: Symbol) -> PendingPrice {
+        env.storage().instance()
+            .get(&DataKey::PendingPrice(asset))
+            .unwrap_or_else(|| panic!("no pending price"))
+    }
+
+    /// Map a Stellar asset contract to the symbol its price is stored under (admin only)
+    pub fn set_stellar_asset(env: Env, token: Address, asset: Symbol) {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+
+        env.storage().instance().set(&DataKey::StellarAsset(token.clone()), &asset);
+        env.storage().instance().set(&DataKey::AssetAddress(asset.clone()), &token);
+
+        env.events().publish((symbol_short!("asset_map"), asset), token);
+    }
+
+    /// Get all registered assets
+    pub fn get_assets(env: Env) -> Vec<Symbol> {
+        env.storage().instance().get(&DataKey::AssetList).unwrap_or(vec![&env])
+    }
+
+    /// Price an asset as `price(base) * ratio` (admin only)
+    pub fn set_derived_asset(env: Env, asset: Symbol, base: Symbol, max_annual_growth_bps: u32) {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+
+        Self::require_registered(&env, &asset);
+        Self::require_registered(&env, &base);
+        if base == asset || env.storage().instance().has(&DataKey::Derived(base.clone())) {
+            panic!("invalid base asset");
+        }
+        // Derivations are one level deep, a base cannot itself become derived
+        if !Self::get_derived_assets(env.clone(), asset.clone()).is_empty() {
+            panic!("asset is a base of derived assets");
+        }
+
+        // Moving to another base drops the asset from the previous base's dependents
+        if let Some(previous) = env.storage().instance().get::<DataKey, DerivedAsset>(&DataKey::Derived(asset.clone())) {
+            let mut dependents = Self::get_derived_assets(env.clone(), previous.base.clone());
+            if let Some(index) = dependents.first_index_of(&asset) {
+                dependents.remove(index);
+            }
+            env.storage().instance().set(&DataKey::DerivedAssets(previous.base), &dependents);
+        }
+
+        let mut dependents = Self::get_derived_assets(env.clone(), base.clone());
+        dependents.push_back(asset.clone());
+        env.storage().instance().set(&DataKey::DerivedAssets(base.clone()), &dependents);
+
+        let derived = DerivedAsset { base, max_annual_growth_bps };
+        env.storage().instance().set(&DataKey::Derived(asset.clone()), &derived);
+        Self::record_derived_observation(&env, &asset);
+
+        env.events().publish((symbol_short!("derived"), asset), derived);
+    }
+
+    /// Report the ratio of a derived asset to its base (admin only). Any ratio is
+    /// recorded, but prices use it capped at the asset's max annual growth from the
+    /// ratio snapshot, which the first reported ratio initializes.
+    pub fn set_ratio(env: Env, asset: Symbol, ratio: i128) {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+
+        Self::get_derived_asset(env.clone(), asset.clone());
+
+        if ratio <= 0 {
+            panic!("ratio must be positive");
+        }
+
+        let current_timestamp = env.ledger().timestamp();
+        if !env.storage().instance().has(&DataKey::RatioSnapshot(asset.clone())) {
+            let snapshot = RatioSnapshot { ratio, timestamp: current_timestamp };
+            env.storage().instance().set(&DataKey::RatioSnapshot(asset.clone()), &snapshot);
+        }
+
+        let ratio_data = RatioData { ratio, timestamp: current_timestamp };
+        env.storage().instance().set(&DataKey::Ratio(asset.clone()), &ratio_data);
+        Self::record_derived_observation(&env, &asset);
+
+        env.events().publish((symbol_short!("ratio_upd"), asset), ratio);
+    }
+
+    /// Get the derivation of an asset price
+    pub fn get_derived_asset(env: Env, asset: Symbol) -> DerivedAsset {
+        env.storage().instance()
+            .get(&DataKey::Derived(asset))
+            .unwrap_or_else(|| panic!("asset is not derived"))
+    }
+
+    /// Get the assets whose price is derived from a base asset
+    pub fn get_derived_assets(env: Env, base: Symbol) -> Vec<Symbol> {
+        env.storage().instance().get(&DataKey::DerivedAssets(base)).unwrap_or(vec![&env])
+    }
+
+    /// Get the ratio of a derived asset to its base
+    pub fn get_ratio(env: Env, asset: Symbol) -> RatioData {
+        env.storage().instance()
+            .get(&DataKey::Ratio(asset))
+            .unwrap_or_else(|| panic!("ratio not found for asset"))
+    }
+
+    /// Set the trusted ratio a derived asset's growth is bounded from (admin only)
+    pub fn set_ratio_snapshot(env: Env, asset: Symbol, ratio: i128, timestamp: u64) {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+
+        Self::get_derived_asset(env.clone(), asset.clone());
+
+        if ratio <= 0 {
+            panic!("ratio must be positive");
+        }
+        if timestamp > env.ledger().timestamp() {
+            panic!("snapshot in the future");
+        }
+
+        let snapshot = RatioSnapshot { ratio, timestamp };
+        env.storage().instance().set(&DataKey::RatioSnapshot(asset.clone()), &snapshot);
+        Self::record_derived_observation(&env, &asset);
+
+        env.events().publish((symbol_short!("ratio_snp"), asset), snapshot);
+    }
+
+    /// Move the snapshot to the current capped ratio (admin only)
+    pub fn refresh_ratio_snapshot(env: Env, asset: Symbol) {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+
+        let snapshot = RatioSnapshot {
+            ratio: Self::get_ratio_info(env.clone(), asset.clone()).capped,
+            timestamp: env.ledger().timestamp(),
+        };
+        env.storage().instance().set(&DataKey::RatioSnapshot(asset.clone()), &snapshot);
+        Self::record_derived_observation(&env, &asset);
+
+        env.events().publish((symbol_short!("ratio_snp"), asset), snapshot);
+    }
+
+    /// Get the trusted ratio a derived asset's growth is bounded from
+    pub fn get_ratio_snapshot(env: Env, asset: Symbol) -> RatioSnapshot {
+        env.storage().instance()
+            .get(&DataKey::RatioSnapshot(asset))
+            .unwrap_or_else(|| panic!("ratio snapshot not set"))
+    }
+
+    /// Get the reported and capped ratio of a derived asset side by side
+    pub fn get_ratio_info(env: Env, asset: Symbol) -> RatioInfo {
+        let derived = Self::get_derived_asset(env.clone(), asset.clone());
+        let reported = Self::get_ratio(env.clone(), asset.clone()).ratio;
+        let max_ratio = Self::max_ratio(&env, &Self::get_ratio_snapshot(env.clone(), asset), derived.max_annual_growth_bps);
+
+        RatioInfo {
+            reported,
+            capped: reported.min(max_ratio),
+            max_ratio,
+            is_capped: reported > max_ratio,
+        }
+    }
+
+    /// Register a (base, quote) pair with its price decimals (admin only)
+    pub fn register_pair(env: Env, base: Symbol, quote: Symbol, decimals: u32) {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+
+        if quote == USD {
+            panic!("usd prices are set per asset");
+        }
+        if base == quote {
+            panic!("pair assets must differ");
+        }
+        if decimals > MAX_PAIR_DECIMALS {
+            panic!("pair decimals too large");
+        }
+        Self::require_registered(&env, &base);
+        Self::require_registered(&env, &quote);
+
+        let key = DataKey::Pair(base.clone(), quote.clone());
+        if env.storage().instance().has(&key) {
+            panic!("pair already registered");
+        }
+
+        let info = PairInfo { base: base.clone(), quote: quote.clone(), decimals };
+        env.storage().instance().set(&key, &info);
+
+        let mut pairs = Self::get_pairs(env.clone());
+        pairs.push_back(info);
+        env.storage().instance().set(&DataKey::PairList, &pairs);
+
+        env.events().publish((symbol_short!("pair_reg"), base, quote), decimals);
+    }
+
+    /// Set the price of a registered pair in its decimals (admin only)
+    pub fn set_pair_price(env: Env, base: Symbol, quote: Symbol, price: i128) {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+
+        Self::get_pair_info(env.clone(), base.clone(), quote.clone());
+
+        if price <= 0 {
+            panic!("price must be positive");
+        }
+
+        let price_data = PriceData { price, conf: 0, timestamp: env.ledger().timestamp() };
+        env.storage().instance().set(&DataKey::PairPrice(base.clone(), quote.clone()), &price_data);
+        env.events().publish((symbol_short!("pair_upd"), base, quote), price);
+    }
+
+    /// Route a registered pair through an intermediate asset when it has no stored price (admin only)
+    pub fn set_pair_route(env: Env, base: Symbol, quote: Symbol, via: Symbol) {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+
+        Self::get_pair_info(env.clone(), base.clone(), quote.clone());
+
+        if via == base || via == quote {
+            panic!("route must use another asset");
+        }
+
+        env.storage().instance().set(&DataKey::PairRoute(base.clone(), quote.clone()), &via);
+        env.events().publish((symbol_short!("pair_rte"), base, quote), via);
+    }
+
+    /// Get the quote asset and decimals of a registered pair
+    pub fn get_pair_info(env: Env, base: Symbol, quote: Symbol) -> PairInfo {
+        env.storage().instance()
+            .get(&DataKey::Pair(base, quote))
+            .unwrap_or_else(|| panic!("pair not registered"))
+    }
+
+    /// Get all registered pairs
+    pub fn get_pairs(env: Env) -> Vec<PairInfo> {
+        env.storage().instance()
+            .get(&DataKey::PairList)
+            .unwrap_or(vec![&env])
+    }
+
+    /// Get the intermediate asset a pair is routed through
+    pub fn get_pair_route(env: Env, base: Symbol, quote: Symbol) -> Symbol {
+        env.storage().instance()
+            .get(&DataKey::PairRoute(base, quote))
+            .unwrap_or_else(|| panic!("pair route not found"))
+    }
+
+    /// Get the price of a base asset in a quote asset, from a stored price,
+    /// the USD prices or the configured route. USD pairs use 7 decimals.
+    pub fn get_pair_price(env: Env, base: Symbol, quote: Symbol) -> PairPrice {
+        let decimals = if quote == USD {
+            DECIMALS
+        } else {
+            Self::get_pair_info(env.clone(), base.clone(), quote.clone()).decimals
+        };
+
+        let (price, price_decimals, timestamp) = Self::resolve_pair(&env, &base, &quote, MAX_ROUTE_HOPS)
+            .unwrap_or_else(|| panic!("pair price not available"));
+
+        PairPrice {
+            price: Self::scale_decimals(price, price_decimals, decimals),
+            decimals,
+            timestamp,
+        }
+    }
+
+    /// Set the ordered price sources of an asset (admin only)
+    pub fn set_price_sources(env: Env, asset: Symbol, sources: Vec<PriceSource>) {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+
+        if sources.is_empty() {
+            panic!("at least one source required");
+        }
+
+        env.storage().instance().set(&DataKey::Sources(asset.clone()), &sources);
+
+        env.events().publish((symbol_short!("sources"), asset), sources);
+    }
+
+    /// Get the ordered price sources of an asset
+    pub fn get_price_sources(env: Env, asset: Symbol) -> Vec<PriceSource> {
+        env.storage().instance()
+            .get(&DataKey::Sources(asset))
+            .unwrap_or(vec![&env, PriceSource::Local])
+    }
+
+    /// Set the max spread between fresh sources in basis points, 0 disables (admin only)
+    pub fn set_max_spread(env: Env, asset: Symbol, spread_bps: u32) {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+
+        env.storage().instance().set(&DataKey::MaxSpread(asset.clone()), &spread_bps);
+        env.events().publish((symbol_short!("max_sprd"), asset), spread_bps);
+    }
+
+    /// Get the max spread between fresh sources in basis points
+    pub fn get_max_spread(env: Env, asset: Symbol) -> u32 {
+        env.storage().instance().get(&DataKey::MaxSpread(asset)).unwrap_or(0)
+    }
+
+    /// Set the max confidence interval relative to price in basis points, 0 disables (admin only)
+    pub fn set_max_confidence(env: Env, asset: Symbol, max_conf_bps: u32) {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+
+        env.storage().instance().set(&DataKey::MaxConfidence(asset.clone()), &max_conf_bps);
+        env.events().publish((symbol_short!("max_conf"), asset), max_conf_bps);
+    }
+
+    /// Get the max confidence interval relative to price in basis points
+    pub fn get_max_confidence(env: Env, asset: Symbol) -> u32 {
+        env.storage().instance().get(&DataKey::MaxConfidence(asset)).unwrap_or(0)
+    }
+
+    /// Get price for an asset in USD (with 7 decimals)
+    pub fn get_price(env: Env, asset: Symbol) -> i128 {
+        Self::get_sourced_price(env, asset).price
+    }
+
+    /// Get price, confidence interval and timestamp of an asset
+    pub fn get_price_with_confidence(env: Env, asset: Symbol) -> PriceData {
+        let sourced = Self::get_sourced_price(env, asset);
+        PriceData {
+            price: sourced.price,
+            conf: sourced.conf,
+            timestamp: sourced.timestamp,
+        }
+    }
+
+    /// Get the first fresh price of an asset along with the source it came from
+    pub fn get_sourced_price(env: Env, asset: Symbol) -> SourcedPrice {
+        match Self::select_price(&env, &asset) {
+            (Some(sourced), None) => sourced,
+            (_, reason) => panic!("{}", reason.unwrap_or("price not found for asset")),
+        }
+    }
+
+    /// Get the best available price of an asset and whether it is safe to use,
+    /// without panicking on stale or missing data
+    pub fn get_price_status(env: Env, asset: Symbol) -> PriceStatus {
+        let (sourced, failure) = Self::select_price(&env, &asset);
+        let is_fresh = failure.is_none();
+        let sourced = sourced.unwrap_or(SourcedPrice {
+            price: 0,
+            conf: 0,
+            timestamp: 0,
+            source: PriceSource::Local,
+        });
+
+        let resumed_at = match &sourced.source {
+            PriceSource::Local => Self::get_resumed_at(env.clone(), asset.clone()),
+            PriceSource::Sep40(oracle, external_asset) => {
+                Self::sep40_resumed_at(&env, oracle, external_asset, Self::get_max_age(env.clone(), asset))
+            }
+        };
+
+        PriceStatus {
+            price: sourced.price,
+            conf: sourced.conf,
+            timestamp: sourced.timestamp,
+            is_fresh,
+            source: sourced.source,
+            resumed_at,
+        }
+    }
+
+    /// Get price with freshness check disabled (for testing)
+    pub fn get_price_unchecked(env: Env, asset: Symbol) -> i128 {
+        let price_data = Self::load_price(&env, &asset)
+            .unwrap_or_else(|| panic!("price not found for asset"));
+
+        price_data.price
+    }
+
+    /// Get multiple prices at once
+    pub fn get_prices(env: Env, assets: Vec<Symbol>) -> Vec<i128> {
+        let mut prices = vec![&env];
+
+        for i in 0..assets.len() {
+            let price = Self::get_price(env.clone(), assets.get(i).unwrap());
+            prices.push_back(price);
+        }
+
+        prices
+    }
+
+    /// Convert amount from one asset to USD value
+    pub fn convert_to_usd(env: Env, asset: Symbol, amount: i128) -> i128 {
+        let price = Self::get_price(env, asset);
+        fixed_mul_floor(amount, price)
+    }
+
+    /// Convert USD value to asset amount
+    pub fn convert_from_usd(env: Env, asset: Symbol, usd_amount: i128) -> i128 {
+        let price = Self::get_price(env, asset);
+        fixed_div_floor(usd_amount, price)
+    }
+
+    /// Convert an amount of one asset into another in a single step. Amounts
+    /// use each asset's registered decimals and the result is rounded down.
+    pub fn convert(env: Env, from_asset: Symbol, to_asset: Symbol, amount: i128) -> i128 {
+        let from_decimals = Self::get_asset_info(env.clone(), from_asset.clone()).decimals;
+        let to_decimals = Self::get_asset_info(env.clone(), to_asset.clone()).decimals;
+
+        let mut from_price = Self::get_price(env.clone(), from_asset);
+        let mut to_price = Self::get_price(env, to_asset);
+        if to_decimals >= from_decimals {
+            from_price *= 10i128.pow(to_decimals - from_decimals);
+        } else {
+            to_price *= 10i128.pow(from_decimals - to_decimals);
+        }
+
+        mul_div_floor(amount, from_price, to_price)
+    }
+
+    /// Get the time-weighted average price over the last `window_secs` seconds
+    pub fn get_twap(env: Env, asset: Symbol, window_secs: u64) -> i128 {
+        if window_secs == 0 {
+            panic!("window must be positive");
+        }
+
+        let state = Self::get_observation_state(&env, &asset);
+        if state.count == 0 {
+            panic!("price not found for asset");
+        }
+
+        let current_timestamp = env.ledger().timestamp();
+        let oldest = Self::get_observation(&env, &asset, &state, state.count - 1);
+        if window_secs > current_timestamp || current_timestamp - window_secs < oldest.timestamp {
+            panic!("window exceeds stored history");
+        }
+        let target = current_timestamp - window_secs;
+
+        let latest = Self::get_observation(&env, &asset, &state, 0);
+        let cumulative_now = Self::cumulative_at(&latest, current_timestamp);
+
+        // Newest observation at or before the start of the window
+        let start = Self::find_observation(&env, &asset, target).unwrap_or(oldest);
+        let cumulative_start = Self::cumulative_at(&start, target);
+
+        (cumulative_now - cumulative_start) / window_secs as i128
+    }
+
+    /// Set how many observations are kept for an asset (admin only)
+    pub fn set_history_size(env: Env, asset: Symbol, size: u32) {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+
+        if !(2..=MAX_HISTORY_SIZE).contains(&size) {
+            panic!("invalid history size");
+        }
+
+        // Compact the newest observations into slots 0..count of the new buffer
+        let state = Self::get_observation_state(&env, &asset);
+        let count = state.count.min(size);
+        let mut kept: Vec<Observation> = vec![&env];
+        for age in (0..count).rev() {
+            kept.push_back(Self::get_observation(&env, &asset, &state, age));
+        }
+        for slot in 0..state.capacity.max(size) {
+            env.storage().persistent().remove(&DataKey::Observation(asset.clone(), slot));
+        }
+        for (slot, observation) in kept.iter().enumerate() {
+            Self::store_observation(&env, &asset, slot as u32, &observation);
+        }
+
+        let new_state = ObservationState { next: count % size, count, capacity: size };
+        Self::store_observation_state(&env, &asset, &new_state);
+
+        env.events().publish((symbol_short!("hist_size"), asset), size);
+    }
+
+    /// Get how many observations are kept for an asset
+    pub fn get_history_size(env: Env, asset: Symbol) -> u32 {
+        Self::get_observation_state(&env, &asset).capacity
+    }
+
+    /// Get the newest observed price at or before a timestamp
+    pub fn get_price_at(env: Env, asset: Symbol, timestamp: u64) -> PriceData {
+        Self::find_observation(&env, &asset, timestamp)
+            .map(|observation| PriceData {
+                price: observation.price,
+                conf: observation.conf,
+                timestamp: observation.timestamp,
+            })
+            .unwrap_or_else(|| panic!("no price at timestamp"))
+    }
+
+    /// Get up to `limit` observed prices, newest first
+    pub fn get_price_history(env: Env, asset: Symbol, limit: u32) -> Vec<PriceData> {
+        let state = Self::get_observation_state(&env, &asset);
+
+        let mut history = vec![&env];
+        for age in 0..limit.min(state.count) {
+            let observation = Self::get_observation(&env, &asset, &state, age);
+            history.push_back(PriceData {
+                price: observation.price,
+                conf: observation.conf,
+                timestamp: observation.timestamp,
+            });
+        }
+        history
+    }
+
+    /// Get when an asset's feed last resumed after going stale (0 if never)
+    pub fn get_resumed_at(env: Env, asset: Symbol) -> u64 {
+        let asset = match env.storage().instance().get::<DataKey, DerivedAsset>(&DataKey::Derived(asset.clone())) {
+            Some(derived) => derived.base,
+            None => asset,
+        };
+        env.storage().instance().get(&DataKey::ResumedAt(asset)).unwrap_or(0)
+    }
+
+    /// Get the timestamp of last price update
+    pub fn get_last_updated(env: Env, asset: Symbol) -> u64 {
+        Self::select_price(&env, &asset).0
+            .unwrap_or_else(|| panic!("price not found for asset"))
+            .timestamp
+    }
+
+    /// Get the last update timestamp together with the configured heartbeat
+    pub fn get_update_info(env: Env, asset: Symbol) -> UpdateInfo {
+        let (sourced, reason) = Self::select_price(&env, &asset);
+        let last_updated = sourced
+            .unwrap_or_else(|| panic!("price not found for asset"))
+            .timestamp;
+
+        UpdateInfo {
+            last_updated,
+            max_age: Self::get_max_age(env.clone(), asset),
+            is_fresh: reason.is_none(),
+        }
+    }
+
+    /// Check if price data is fresh (within the asset's heartbeat)
+    pub fn is_price_fresh(env: Env, asset: Symbol) -> bool {
+        Self::select_price(&env, &asset).1.is_none()
+    }
+
+    // Internal helper functions
+
+    // First fresh and usable price of an asset. On failure returns the best
+    // unusable price, if any source had one, along with the reason.
+    fn select_price(env: &Env, asset: &Symbol) -> (Option<SourcedPrice>, Option<&'static str>) {
+        let max_age = Self::get_max_age(env.clone(), asset.clone());
+        let max_spread = Self::get_max_spread(env.clone(), asset.clone()) as i128;
+        let max_conf = Self::get_max_confidence(env.clone(), asset.clone()) as i128;
+        let current_timestamp = env.ledger().timestamp();
+
+        let mut selected: Option<SourcedPrice> = None;
+        let mut fallback: Option<SourcedPrice> = None;
+        let mut too_uncertain = false;
+
+        for source in Self::get_price_sources(env.clone(), asset.clone()).iter() {
+            let price_data = match Self::read_source(env, asset, &source) {
+                Some(price_data) => price_data,
+                None => continue,
+            };
+            let sourced = SourcedPrice {
+                price: price_data.price,
+                conf: price_data.conf,
+                timestamp: price_data.timestamp,
+                source,
+            };
+            if fallback.is_none() {
+                fallback = Some(sourced.clone());
+            }
+
+            // Check if price is fresh (within the asset's heartbeat)
+            if current_timestamp > sourced.timestamp + max_age {
+                continue;
+            }
+
+            // Prices with a too wide confidence interval are unusable
+            if max_conf > 0 && sourced.conf * 10000 > sourced.price * max_conf {
+                too_uncertain = true;
+                continue;
+            }
+
+            match &selected {
+                None => {
+                    selected = Some(sourced);
+                    if max_spread == 0 {
+                        break;
+                    }
+                }
+                Some(primary) => {
+                    let spread = to_bps_floor((sourced.price - primary.price).abs(), primary.price);
+                    if spread > max_spread {
+                        return (selected, Some("price sources diverge"));
+                    }
+                }
+            }
+        }
+
+        match selected {
+            Some(sourced) => {
+                Self::check_staleness(env, asset, sourced.timestamp, max_age);
+                (Some(sourced), None)
+            }
+            None if too_uncertain => (fallback, Some("price confidence too wide")),
+            None if fallback.is_some() => (fallback, Some("price data is stale")),
+            None => (None, Some("price not found for asset")),
+        }
+    }
+
+    // Report an asset losing the quorum of feeders with a fresh submission. Rounds
+    // still filling up are not reported, only a drop from at least the quorum.
+    fn check_quorum(env: &Env, asset: &Symbol) {
+        let max_age = Self::get_max_age(env.clone(), asset.clone());
+        let current_timestamp = env.ledger().timestamp();
+        let mut active_feeders = 0u32;
+        for submission in Self::get_submissions(env.clone(), asset.clone()).iter() {
+            if current_timestamp <= submission.timestamp + max_age {
+                active_feeders += 1;
+            }
+        }
+
+        let key = DataKey::ActiveFeeders(asset.clone());
+        let old_active_feeders: u32 = env.storage().instance().get(&key).unwrap_or(0);
+        let quorum = Self::get_quorum(env.clone());
+        if active_feeders < quorum && old_active_feeders >= quorum {
+            env.events().publish(
+                (symbol_short!("alert"), symbol_short!("quorum"), asset.clone()),
+                QuorumAlert { asset: asset.clone(), old_active_feeders, active_feeders, quorum },
+            );
+        }
+        env.storage().instance().set(&key, &active_feeders);
+    }
+
+    // Report a fresh price read close to its heartbeat
+    fn check_staleness(env: &Env, asset: &Symbol, last_updated: u64, max_age: u64) {
+        let warning_secs = Self::get_alert_config(env.clone(), asset.clone()).staleness_warning_secs;
+        let read_at = env.ledger().timestamp();
+        if warning_secs > 0 && read_at + warning_secs > last_updated + max_age {
+            env.events().publish(
+                (symbol_short!("alert"), symbol_short!("stale"), asset.clone()),
+                StalenessAlert { asset: asset.clone(), last_updated, read_at, max_age, warning_secs },
+            );
+        }
+    }
+
+    // Price of an asset from one source, scaled to 7 decimals
+    fn read_source(env: &Env, asset: &Symbol, source: &PriceSource) -> Option<PriceData> {
+        match source {
+            PriceSource::Local => Self::load_price(env, asset),
+            PriceSource::Sep40(oracle, external_asset) => {
+                // An unreachable external oracle is skipped like a stale one
+                let client = Sep40Client::new(env, oracle);
+                let price_data = match client.try_lastprice(external_asset) {
+                    Ok(Ok(Some(price_data))) => price_data,
+                    _ => return None,
+                };
+                let decimals = match client.try_decimals() {
+                    Ok(Ok(decimals)) => decimals,
+                    _ => return None,
+                };
+
+                let price = Self::scale_decimals(price_data.price, decimals, DECIMALS);
+                Some(PriceData { price, conf: 0, timestamp: price_data.timestamp })
+            }
+        }
+    }
+
+    // External feeds keep no outage record for us, so the latest recovery is found as
+    // the first price after a gap longer than the max age in the feed's recent history.
+    // Recoveries followed by more than SEP40_OUTAGE_RECORDS updates are not seen.
+    fn sep40_resumed_at(env: &Env, oracle: &Address, external_asset: &Asset, max_age: u64) -> u64 {
+        let history = match Sep40Client::new(env, oracle).try_prices(external_asset, &SEP40_OUTAGE_RECORDS) {
+            Ok(Ok(Some(history))) => history,
+            _ => return 0,
+        };
+        for i in 1..history.len() {
+            let newer = history.get(i - 1).unwrap();
+            if newer.timestamp > history.get(i).unwrap().timestamp.saturating_add(max_age) {
+                return newer.timestamp;
+            }
+        }
+        0
+    }
+
+    // Fresh price of a pair with its decimals, directly or through its route
+    fn resolve_pair(env: &Env, base: &Symbol, quote: &Symbol, hops: u32) -> Option<(i128, u32, u64)> {
+        if let Some(price) = Self::direct_pair_price(env, base, quote) {
+            return Some(price);
+        }
+        if let Some((price, decimals, timestamp)) = Self::direct_pair_price(env, quote, base) {
+            let one = 10i128.pow(decimals);
+            return Some((mul_div_floor(one, one, price), decimals, timestamp));
+        }
+        if hops == 0 {
+            return None;
+        }
+
+        let via: Symbol = env.storage().instance().get(&DataKey::PairRoute(base.clone(), quote.clone()))?;
+        let (base_price, base_decimals, base_timestamp) = Self::resolve_pair(env, base, &via, hops - 1)?;
+        let (via_price, via_decimals, via_timestamp) = Self::resolve_pair(env, &via, quote, hops - 1)?;
+        Some((
+            mul_div_floor(base_price, via_price, 10i128.pow(via_decimals)),
+            base_decimals,
+            base_timestamp.min(via_timestamp),
+        ))
+    }
+
+    // Fresh stored price of a pair, with USD quotes read from the asset prices
+    fn direct_pair_price(env: &Env, base: &Symbol, quote: &Symbol) -> Option<(i128, u32, u64)> {
+        if *quote == USD {
+            // Stale, too uncertain or diverging prices come back with a failure reason
+            return match Self::select_price(env, base) {
+                (Some(price), None) => Some((price.price, DECIMALS, price.timestamp)),
+                _ => None,
+            };
+        }
+
+        let info = env.storage().instance().get::<DataKey, PairInfo>(&DataKey::Pair(base.clone(), quote.clone()))?;
+        let price_data = env.storage().instance().get::<DataKey, PriceData>(&DataKey::PairPrice(base.clone(), quote.clone()))?;
+        if env.ledger().timestamp() > price_data.timestamp + Self::get_max_age(env.clone(), base.clone()) {
+            return None;
+        }
+        Some((price_data.price, info.decimals, price_data.timestamp))
+    }
+
+    fn scale_decimals(value: i128, from: u32, to: u32) -> i128 {
+        if from >= to {
+            value / 10i128.pow(from - to)
+        } else {
+            value * 10i128.pow(to - from)
+        }
+    }
+
+    // Stored price of an asset, resolving derived assets through their base
+    fn load_price(env: &Env, asset: &Symbol) -> Option<PriceData> {
+        match env.storage().instance().get::<DataKey, DerivedAsset>(&DataKey::Derived(asset.clone())) {
+            Some(derived) => {
+                let base = env.storage().instance().get::<DataKey, PriceData>(&DataKey::Price(derived.base))?;
+                let reported = env.storage().instance().get::<DataKey, RatioData>(&DataKey::Ratio(asset.clone()))?.ratio;
+                let snapshot = env.storage().instance().get::<DataKey, RatioSnapshot>(&DataKey::RatioSnapshot(asset.clone()))?;
+                let ratio = reported.min(Self::max_ratio(env, &snapshot, derived.max_annual_growth_bps));
+                Some(PriceData {
+                    price: fixed_mul_floor(base.price, ratio),
+                    conf: fixed_mul_ceil(base.conf, ratio),
+                    timestamp: base.timestamp,
+                })
+            }
+            None => env.storage().instance().get(&DataKey::Price(asset.clone())),
+        }
+    }
+
+    // Highest ratio allowed at the current time, growing linearly from the snapshot
+    fn max_ratio(env: &Env, snapshot: &RatioSnapshot, max_annual_growth_bps: u32) -> i128 {
+        let elapsed = env.ledger().timestamp().saturating_sub(snapshot.timestamp) as i128;
+        snapshot.ratio + mul_div_floor(
+            snapshot.ratio,
+            max_annual_growth_bps as i128 * elapsed,
+            BPS_DENOMINATOR * SECONDS_PER_YEAR as i128,
+        )
+    }
+
+    // Map a SEP-40 asset to the symbol prices are stored under
+    fn resolve_asset(env: &Env, asset: Asset) -> Option<Symbol> {
+        match asset {
+            Asset::Stellar(address) => env.storage().instance().get(&DataKey::StellarAsset(address)),
+            Asset::Other(symbol) => Some(symbol),
+        }
+    }
+
+    fn get_observation_state(env: &Env, asset: &Symbol) -> ObservationState {
+        env.storage().persistent()
+            .get(&DataKey::ObservationState(asset.clone()))
+            .unwrap_or(ObservationState { next: 0, count: 0, capacity: DEFAULT_HISTORY_SIZE })
+    }
+
+    // Observation by age, 0 being the newest
+    fn get_observation(env: &Env, asset: &Symbol, state: &ObservationState, age: u32) -> Observation {
+        let slot = (state.next + state.capacity - 1 - age) % state.capacity;
+        let key = DataKey::Observation(asset.clone(), slot);
+        let observation = env.storage().persistent().get(&key).unwrap();
+        env.storage().persistent().extend_ttl(&key, HISTORY_LIFETIME_THRESHOLD, HISTORY_BUMP_AMOUNT);
+        observation
+    }
+
+    // History lives in persistent storage so it does not grow the instance entry
+    fn store_observation(env: &Env, asset: &Symbol, slot: u32, observation: &Observation) {
+        let key = DataKey::Observation(asset.clone(), slot);
+        env.storage().persistent().set(&key, observation);
+        env.storage().persistent().extend_ttl(&key, HISTORY_LIFETIME_THRESHOLD, HISTORY_BUMP_AMOUNT);
+    }
+
+    fn store_observation_state(env: &Env, asset: &Symbol, state: &ObservationState) {
+        let key = DataKey::ObservationState(asset.clone());
+        env.storage().persistent().set(&key, state);
+        env.storage().persistent().extend_ttl(&key, HISTORY_LIFETIME_THRESHOLD, HISTORY_BUMP_AMOUNT);
+    }
+
+    // Newest observation at or before a timestamp
+    fn find_observation(env: &Env, asset: &Symbol, timestamp: u64) -> Option<Observation> {
+        let state = Self::get_observation_state(env, asset);
+        for age in 0..state.count {
+            let observation = Self::get_observation(env, asset, &state, age);
+            if observation.timestamp <= timestamp {
+                return Some(observation);
+            }
+        }
+        None
+    }
+
+    fn cumulative_at(observation: &Observation, timestamp: u64) -> i128 {
+        observation.cumulative + observation.price * (timestamp - observation.timestamp) as i128
+    }
+
+    fn record_observation(env: &Env, asset: &Symbol, price: i128, conf: i128, timestamp: u64) {
+        let mut state = Self::get_observation_state(env, asset);
+
+        if state.count > 0 {
+            let latest = Self::get_observation(env, asset, &state, 0);
+
+            // Several updates within one ledger replace the newest observation
+            if latest.timestamp == timestamp {
+                let slot = (state.next + state.capacity - 1) % state.capacity;
+                let observation = Observation { price, conf, timestamp, cumulative: latest.cumulative };
+                Self::store_observation(env, asset, slot, &observation);
+                return;
+            }
+
+            let observation = Observation {
+                price,
+                conf,
+                timestamp,
+                cumulative: Self::cumulative_at(&latest, timestamp),
+            };
+            Self::store_observation(env, asset, state.next, &observation);
+        } else {
+            let observation = Observation { price, conf, timestamp, cumulative: 0 };
+            Self::store_observation(env, asset, state.next, &observation);
+        }
+
+        state.next = (state.next + 1) % state.capacity;
+        if state.count < state.capacity {
+            state.count += 1;
+        }
+        Self::store_observation_state(env, asset, &state);
+    }
+
+    // Derived prices are observed at their base's timestamp, so a ratio change
+    // replaces the observation of the current base price
+    fn record_derived_observation(env: &Env, asset: &Symbol) {
+        if let Some(price_data) = Self::load_price(env, asset) {
+            Self::record_observation(env, asset, price_data.price, price_data.conf, price_data.timestamp);
+        }
+    }
+
+    fn median(prices: Vec<i128>) -> i128 {
+        // Insertion sort, submission counts are small
+        let mut sorted: Vec<i128> = Vec::new(prices.env());
+        for price in prices.iter() {
+            let mut index = sorted.len();
+            for i in 0..sorted.len() {
+                if price < sorted.get(i).unwrap() {
+                    index = i;
+                    break;
+                }
+            }
+            sorted.insert(index, price);
+        }
+
+        let mid = sorted.len() / 2;
+        if sorted.len() % 2 == 1 {
+            sorted.get(mid).unwrap()
+        } else {
+            (sorted.get(mid - 1).unwrap() + sorted.get(mid).unwrap()) / 2
+        }
+    }
+
+    fn require_registered(env: &Env, asset: &Symbol) {
+        if !env.storage().instance().has(&DataKey::AssetInfo(asset.clone())) {
+            panic!("asset not registered");
+        }
+    }
+
+    // `timestamp` is when the price was observed, which signed updates carry themselves
+    fn set_price_internal(env: Env, asset: Symbol, price: i128, conf: i128, timestamp: u64, proposers: Vec<Address>) {
+        Self::require_registered(&env, &asset);
+
+        if price <= 0 {
+            panic!("price must be positive");
+        }
+
+        if conf < 0 {
+            panic!("confidence must not be negative");
+        }
+        if conf >= price {
+            panic!("confidence must be below price");
+        }
+
+        if env.storage().instance().has(&DataKey::Derived(asset.clone())) {
+            panic!("asset price is derived");
+        }
+
+        if let Some(current) = env.storage().instance().get::<DataKey, PriceData>(&DataKey::Price(asset.clone())) {
+            let deviation_bps = to_bps_floor((price - current.price).abs(), current.price).min(u32::MAX as i128) as u32;
+
+            // Large moves are reported even when they are within the circuit breaker
+            let soft_deviation = Self::get_alert_config(env.clone(), asset.clone()).soft_deviation_bps;
+            if soft_deviation > 0 && deviation_bps > soft_deviation {
+                env.events().publish(
+                    (symbol_short!("alert"), symbol_short!("deviation"), asset.clone()),
+                    DeviationAlert {
+                        asset: asset.clone(),
+                        old_price: current.price,
+                        new_price: price,
+                        deviation_bps,
+                        threshold_bps: soft_deviation,
+                    },
+                );
+            }
+
+            // Updates moving too far from the current price need a second confirmation
+            let max_deviation = Self::get_max_deviation(env.clone(), asset.clone());
+            if max_deviation > 0 && deviation_bps > max_deviation {
+                let pending = PendingPrice {
+                    price,
+                    conf,
+                    timestamp,
+                    proposers,
+                    deviation_bps,
+                };
+                env.storage().instance().set(&DataKey::PendingPrice(asset.clone()), &pending);
+
+                env.events().publish(
+                    (symbol_short!("price_brk"), asset),
+                    (current.price, price, deviation_bps, max_deviation),
+                );
+                return;
+            }
+        }
+
+        // A regular update supersedes any pending one
+        env.storage().instance().remove(&DataKey::PendingPrice(asset.clone()));
+        Self::publish_price(&env, &asset, price, conf, timestamp);
+    }
+
+    // Outdated proposals must not be published as a fresh price
+    fn require_pending_fresh(env: &Env, asset: &Symbol, pending: &PendingPrice) {
+        if env.ledger().timestamp() > pending.timestamp + Self::get_max_age(env.clone(), asset.clone()) {
+            panic!("pending price expired");
+        }
+    }
+
+    fn publish_price(env: &Env, asset: &Symbol, price: i128, conf: i128, timestamp: u64) {
+        let current_timestamp = env.ledger().timestamp();
+
+        // Remember when a stale feed comes back so consumers can hold off on acting
+        if let Some(previous) = env.storage().instance().get::<DataKey, PriceData>(&DataKey::Price(asset.clone())) {
+            if current_timestamp > previous.timestamp + Self::get_max_age(env.clone(), asset.clone()) {
+                env.storage().instance().set(&DataKey::ResumedAt(asset.clone()), &current_timestamp);
+                env.events().publish((symbol_short!("resumed"), asset.clone()), previous.timestamp);
+            }
+        }
+
+        let price_data = PriceData {
+            price,
+            conf,
+            timestamp,
+        };
+
+        env.storage().instance().set(&DataKey::Price(asset.clone()), &price_data);
+
+        Self::record_observation(env, asset, price, conf, timestamp);
+        for derived in Self::get_derived_assets(env.clone(), asset.clone()).iter() {
+            Self::record_derived_observation(env, &derived);
+        }
+
+        env.events().publish((symbol_short!("price_upd"), asset.clone()), price);
+    }
+}
+
+#[contractimpl]
+impl Sep40 for PriceOracle {
+    fn base(env: Env) -> Asset {
+        Asset::Other(Symbol::new(&env, "USD"))
+    }
+
+    fn assets(env: Env) -> Vec<Asset> {
+        let mut assets = vec![&env];
+        for symbol in Self::get_assets(env.clone()).iter() {
+            match env.storage().instance().get::<DataKey, Address>(&DataKey::AssetAddress(symbol.clone())) {
+                Some(address) => assets.push_back(Asset::Stellar(address)),
+                None => assets.push_back(Asset::Other(symbol)),
+            }
+        }
+        assets
+    }
+
+    fn decimals(_env: Env) -> u32 {
+        DECIMALS
+    }
+
+    fn resolution(_env: Env) -> u32 {
+        RESOLUTION
+    }
+
+    fn lastprice(env: Env, asset: Asset) -> Option<sep40::PriceData> {
+        let symbol = Self::resolve_asset(&env, asset)?;
+        Self::load_price(&env, &symbol)
+            .map(|price_data| sep40::PriceData { price: price_data.price, timestamp: price_data.timestamp })
+    }
+
+    fn price(env: Env, asset: Asset, timestamp: u64) -> Option<sep40::PriceData> {
+        let symbol = Self::resolve_asset(&env, asset)?;
+        Self::find_observation(&env, &symbol, timestamp)
+            .map(|observation| sep40::PriceData { price: observation.price, timestamp: observation.timestamp })
+    }
+
+    fn prices(env: Env, asset: Asset, records: u32) -> Option<Vec<sep40::PriceData>> {
+        let symbol = Self::resolve_asset(&env, asset)?;
+        let history = Self::get_price_history(env.clone(), symbol, records);
+        if history.is_empty() {
+            return None;
+        }
+
+        let mut records = vec![&env];
+        for price_data in history.iter() {
+            records.push_back(sep40::PriceData { price: price_data.price, timestamp: price_data.timestamp });
+        }
+        Some(records)
     }
 }
 
+mod sep40;
 mod test; 
\ No newline at end of file
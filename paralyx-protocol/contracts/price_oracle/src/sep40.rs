@@ -0,0 +1,42 @@
+//! SEP-40 oracle interface, so other Stellar protocols can consume this
+//! oracle and the lending pool can read third-party SEP-40 feeds.
+
+use soroban_sdk::{contractclient, contracttype, Address, Env, Symbol, Vec};
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Asset {
+    Stellar(Address),
+    Other(Symbol),
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PriceData {
+    pub price: i128,
+    pub timestamp: u64,
+}
+
+#[contractclient(name = "Sep40Client")]
+pub trait Sep40 {
+    /// Base asset all prices are quoted in
+    fn base(env: Env) -> Asset;
+
+    /// All assets quoted by the oracle
+    fn assets(env: Env) -> Vec<Asset>;
+
+    /// Number of decimals of quoted prices
+    fn decimals(env: Env) -> u32;
+
+    /// Default update period in seconds
+    fn resolution(env: Env) -> u32;
+
+    /// Latest price of an asset
+    fn lastprice(env: Env, asset: Asset) -> Option<PriceData>;
+
+    /// Price of an asset at or before a timestamp
+    fn price(env: Env, asset: Asset, timestamp: u64) -> Option<PriceData>;
+
+    /// Last `records` prices of an asset, newest first
+    fn prices(env: Env, asset: Asset, records: u32) -> Option<Vec<PriceData>>;
+}
@@ -1,12 +1,26 @@
 #![cfg(test)]
 
 use super::*;
-use soroban_sdk::{testutils::{Address as _, MockAuth, MockAuthInvoke}, symbol_short, Address, Env, IntoVal};
+use soroban_sdk::{testutils::{Address as _, Events, Ledger, MockAuth, MockAuthInvoke}, symbol_short, Address, Env, IntoVal, String, Symbol, TryFromVal, Val};
 
 fn create_oracle_contract<'a>(e: &Env) -> Address {
     e.register_contract(None, PriceOracle {})
 }
 
+// Registers the assets used in these tests and seeds stETH, XLM and USDC prices
+fn initialize_with_mock_prices(client: &PriceOracleClient, admin: &Address) {
+    let env = &client.env;
+    client.initialize(admin);
+
+    for (asset, decimals) in [("stETH", 18u32), ("ETH", 18), ("rETH", 18), ("BTC", 8), ("XLM", 7), ("USDC", 7)] {
+        client.register_asset(&Symbol::new(env, asset), &decimals, &String::from_str(env, asset));
+    }
+
+    client.set_price(&symbol_short!("stETH"), &1500_0000000i128); // $1500
+    client.set_price(&symbol_short!("XLM"), &12_0000000i128);     // $0.12
+    client.set_price(&symbol_short!("USDC"), &1_0000000i128);     // $1.00
+}
+
 #[test]
 fn test_initialize() {
     let env = Env::default();
@@ -41,7 +55,7 @@ fn test_set_and_get_price() {
     let client = PriceOracleClient::new(&env, &contract_id);
     let admin = Address::generate(&env);
 
-    client.initialize(&admin);
+    initialize_with_mock_prices(&client, &admin);
 
     let asset = symbol_short!("stETH");
     let price = 1500_0000000i128; // $1500 with 7 decimals
@@ -72,7 +86,7 @@ fn test_set_negative_price() {
     let client = PriceOracleClient::new(&env, &contract_id);
     let admin = Address::generate(&env);
 
-    client.initialize(&admin);
+    initialize_with_mock_prices(&client, &admin);
 
     let asset = symbol_short!("stETH");
     let negative_price = -100_0000000i128;
@@ -113,7 +127,7 @@ fn test_batch_set_prices() {
     let client = PriceOracleClient::new(&env, &contract_id);
     let admin = Address::generate(&env);
 
-    client.initialize(&admin);
+    initialize_with_mock_prices(&client, &admin);
 
     let assets = soroban_sdk::vec![
         &env,
@@ -156,7 +170,7 @@ fn test_batch_set_prices_length_mismatch() {
     let client = PriceOracleClient::new(&env, &contract_id);
     let admin = Address::generate(&env);
 
-    client.initialize(&admin);
+    initialize_with_mock_prices(&client, &admin);
 
     let assets = soroban_sdk::vec![&env, symbol_short!("stETH")];
     let prices = soroban_sdk::vec![
@@ -187,7 +201,7 @@ fn test_price_freshness() {
     let client = PriceOracleClient::new(&env, &contract_id);
     let admin = Address::generate(&env);
 
-    client.initialize(&admin);
+    initialize_with_mock_prices(&client, &admin);
 
     let asset = symbol_short!("stETH");
     let price = 1500_0000000i128;
@@ -221,7 +235,7 @@ fn test_usd_conversions() {
     let client = PriceOracleClient::new(&env, &contract_id);
     let admin = Address::generate(&env);
 
-    client.initialize(&admin);
+    initialize_with_mock_prices(&client, &admin);
 
     let asset = symbol_short!("stETH");
     let price = 1500_0000000i128; // $1500
@@ -249,4 +263,851 @@ fn test_usd_conversions() {
     let asset_amount_converted = client.convert_from_usd(&asset, &usd_amount);
     let expected_asset = 5000000i128; // $750 / $1500 = 0.5 stETH
     assert_eq!(asset_amount_converted, expected_asset);
-} 
\ No newline at end of file
+} 
+#[test]
+fn test_feeder_median() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = create_oracle_contract(&env);
+    let client = PriceOracleClient::new(&env, &contract_id);
+    let admin = Address::generate(&env);
+    let feeder_a = Address::generate(&env);
+    let feeder_b = Address::generate(&env);
+    let feeder_c = Address::generate(&env);
+
+    initialize_with_mock_prices(&client, &admin);
+    client.add_feeder(&feeder_a);
+    client.add_feeder(&feeder_b);
+    client.add_feeder(&feeder_c);
+    assert!(client.try_set_quorum(&1u32).is_err());
+    assert!(client.try_set_quorum(&4u32).is_err());
+    client.set_quorum(&3u32);
+
+    // Removing a feeder cannot leave the quorum out of reach
+    assert!(client.try_remove_feeder(&feeder_c).is_err());
+    assert_eq!(client.get_feeders().len(), 3);
+
+    let asset = symbol_short!("stETH");
+
+    client.submit_price(&feeder_a, &asset, &1600_0000000i128);
+    client.submit_price(&feeder_b, &asset, &1_0000000i128); // Compromised feeder
+
+    // No quorum yet, the initial price is still in place
+    assert_eq!(client.get_price(&asset), 1500_0000000i128);
+    assert_eq!(client.get_submissions(&asset).len(), 2);
+    assert_eq!(client.get_submission(&asset, &feeder_b).price, 1_0000000i128);
+
+    client.submit_price(&feeder_c, &asset, &1620_0000000i128);
+
+    assert_eq!(client.get_price(&asset), 1600_0000000i128);
+    assert_eq!(client.get_round(&asset), 1u32);
+}
+
+#[test]
+fn test_feeder_rate_limit_and_stats() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = create_oracle_contract(&env);
+    let client = PriceOracleClient::new(&env, &contract_id);
+    let admin = Address::generate(&env);
+    let feeder = Address::generate(&env);
+
+    initialize_with_mock_prices(&client, &admin);
+    client.add_feeder(&feeder);
+    client.set_min_update_interval(&symbol_short!("stETH"), &10u32);
+
+    let stats = client.get_feeder_stats(&feeder);
+    assert_eq!(stats.submissions, 0u32);
+    assert_eq!(stats.last_ledger, 0u32);
+
+    env.ledger().with_mut(|li| {
+        li.sequence_number = 100;
+        li.timestamp = 1000;
+    });
+    client.submit_price(&feeder, &symbol_short!("stETH"), &1510_0000000i128);
+    assert_eq!(client.get_submission(&symbol_short!("stETH"), &feeder).ledger, 100u32);
+
+    // A lone feeder does not reach the default quorum
+    assert_eq!(client.get_price(&symbol_short!("stETH")), 1500_0000000i128);
+
+    // Too soon for the same asset, other assets are not limited
+    env.ledger().with_mut(|li| li.sequence_number = 105);
+    assert!(client.try_submit_price(&feeder, &symbol_short!("stETH"), &1520_0000000i128).is_err());
+    client.submit_price(&feeder, &symbol_short!("USDC"), &1_0000000i128);
+
+    env.ledger().with_mut(|li| {
+        li.sequence_number = 110;
+        li.timestamp = 1050;
+    });
+    client.submit_price(&feeder, &symbol_short!("stETH"), &1520_0000000i128);
+    assert_eq!(client.get_submission(&symbol_short!("stETH"), &feeder).price, 1520_0000000i128);
+
+    let stats = client.get_feeder_stats(&feeder);
+    assert_eq!(stats.submissions, 3u32);
+    assert_eq!(stats.last_ledger, 110u32);
+    assert_eq!(stats.last_timestamp, 1050u64);
+}
+
+#[test]
+#[should_panic(expected = "not a feeder")]
+fn test_submit_price_not_feeder() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = create_oracle_contract(&env);
+    let client = PriceOracleClient::new(&env, &contract_id);
+    let admin = Address::generate(&env);
+    let stranger = Address::generate(&env);
+
+    initialize_with_mock_prices(&client, &admin);
+    client.submit_price(&stranger, &symbol_short!("stETH"), &1_0000000i128);
+}
+
+#[test]
+fn test_twap() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = create_oracle_contract(&env);
+    let client = PriceOracleClient::new(&env, &contract_id);
+    let admin = Address::generate(&env);
+
+    initialize_with_mock_prices(&client, &admin);
+
+    let asset = symbol_short!("ETH");
+
+    env.ledger().with_mut(|li| li.timestamp = 1000);
+    client.set_price(&asset, &100_0000000i128);
+
+    env.ledger().with_mut(|li| li.timestamp = 1100);
+    client.set_price(&asset, &200_0000000i128);
+
+    env.ledger().with_mut(|li| li.timestamp = 1200);
+
+    // 100s at $100 and 100s at $200
+    assert_eq!(client.get_twap(&asset, &200u64), 150_0000000i128);
+    // Only the last 100s at $200
+    assert_eq!(client.get_twap(&asset, &100u64), 200_0000000i128);
+    // 50s at $100 and 100s at $200
+    assert_eq!(client.get_twap(&asset, &150u64), 1666666666i128);
+}
+
+#[test]
+#[should_panic(expected = "window exceeds stored history")]
+fn test_twap_window_too_long() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = create_oracle_contract(&env);
+    let client = PriceOracleClient::new(&env, &contract_id);
+    let admin = Address::generate(&env);
+
+    initialize_with_mock_prices(&client, &admin);
+
+    let asset = symbol_short!("ETH");
+
+    env.ledger().with_mut(|li| li.timestamp = 1000);
+    client.set_price(&asset, &100_0000000i128);
+
+    env.ledger().with_mut(|li| li.timestamp = 1200);
+    client.get_twap(&asset, &300u64);
+}
+
+#[test]
+fn test_per_asset_max_age() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = create_oracle_contract(&env);
+    let client = PriceOracleClient::new(&env, &contract_id);
+    let admin = Address::generate(&env);
+
+    initialize_with_mock_prices(&client, &admin);
+
+    let usdc = symbol_short!("USDC");
+    let steth = symbol_short!("stETH");
+    client.set_max_age(&usdc, &86400u64);
+
+    env.ledger().with_mut(|li| li.timestamp = 1000);
+    client.set_price(&usdc, &1_0000000i128);
+    client.set_price(&steth, &1500_0000000i128);
+
+    // Two hours later only the long-heartbeat USDC price is still fresh
+    env.ledger().with_mut(|li| li.timestamp = 1000 + 7200);
+    assert!(client.is_price_fresh(&usdc));
+    assert!(!client.is_price_fresh(&steth));
+    assert_eq!(client.get_price(&usdc), 1_0000000i128);
+
+    let info = client.get_update_info(&steth);
+    assert_eq!(info.last_updated, 1000u64);
+    assert_eq!(info.max_age, 3600u64);
+    assert!(!info.is_fresh);
+    assert_eq!(client.get_update_info(&usdc).max_age, 86400u64);
+}
+
+#[test]
+fn test_deviation_circuit_breaker() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = create_oracle_contract(&env);
+    let client = PriceOracleClient::new(&env, &contract_id);
+    let admin = Address::generate(&env);
+    let feeder_a = Address::generate(&env);
+    let feeder_b = Address::generate(&env);
+    let feeder_c = Address::generate(&env);
+
+    initialize_with_mock_prices(&client, &admin);
+    client.add_feeder(&feeder_a);
+    client.add_feeder(&feeder_b);
+    client.add_feeder(&feeder_c);
+
+    let asset = symbol_short!("stETH");
+    client.set_max_deviation(&asset, &1000u32); // 10%
+
+    // $1500 -> $1 is held back
+    client.submit_price(&feeder_a, &asset, &1_0000000i128);
+    client.submit_price(&feeder_c, &asset, &1_0000000i128);
+    assert_eq!(client.get_price(&asset), 1500_0000000i128);
+
+    let pending = client.get_pending_price(&asset);
+    assert_eq!(pending.price, 1_0000000i128);
+    assert_eq!(pending.proposers, soroban_sdk::vec![&env, feeder_a.clone(), feeder_c.clone()]);
+    assert_eq!(pending.deviation_bps, 9993u32);
+
+    // No feeder of the breaching round can confirm it
+    assert!(client.try_confirm_pending_price(&feeder_a, &asset).is_err());
+    assert!(client.try_confirm_pending_price(&feeder_c, &asset).is_err());
+
+    // The confirming feeder must have observed a similar price itself
+    assert!(client.try_confirm_pending_price(&feeder_b, &asset).is_err());
+    client.submit_price(&feeder_b, &asset, &1500_0000000i128);
+    assert!(client.try_confirm_pending_price(&feeder_b, &asset).is_err());
+    client.submit_price(&feeder_b, &asset, &1_0200000i128);
+
+    client.confirm_pending_price(&feeder_b, &asset);
+    assert_eq!(client.get_price(&asset), 1_0000000i128);
+
+    // Small moves are published directly
+    client.submit_price(&feeder_a, &asset, &1_0500000i128);
+    assert_eq!(client.get_price(&asset), 1_0350000i128);
+}
+
+#[test]
+fn test_override_pending_price() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = create_oracle_contract(&env);
+    let client = PriceOracleClient::new(&env, &contract_id);
+    let admin = Address::generate(&env);
+
+    initialize_with_mock_prices(&client, &admin);
+
+    let asset = symbol_short!("stETH");
+    client.set_max_deviation(&asset, &1000u32);
+
+    client.set_price(&asset, &3000_0000000i128);
+    client.override_pending_price(&asset, &false);
+    assert_eq!(client.get_price(&asset), 1500_0000000i128);
+    assert!(client.try_get_pending_price(&asset).is_err());
+
+    client.set_price(&asset, &3000_0000000i128);
+    client.override_pending_price(&asset, &true);
+    assert_eq!(client.get_price(&asset), 3000_0000000i128);
+
+    // Huge jumps are held back instead of wrapping the deviation
+    client.set_price(&asset, &1_000_000_000_000_000_000i128);
+    assert_eq!(client.get_price(&asset), 3000_0000000i128);
+    assert_eq!(client.get_pending_price(&asset).deviation_bps, u32::MAX);
+
+    // Outdated proposals can only be discarded
+    env.ledger().with_mut(|li| li.timestamp += 3601);
+    client.set_price(&asset, &3000_0000000i128);
+    client.set_price(&asset, &6000_0000000i128);
+    env.ledger().with_mut(|li| li.timestamp += 3601);
+    assert!(client.try_override_pending_price(&asset, &true).is_err());
+    client.override_pending_price(&asset, &false);
+    assert!(client.try_get_pending_price(&asset).is_err());
+}
+
+#[test]
+fn test_sep40_interface() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = create_oracle_contract(&env);
+    let client = PriceOracleClient::new(&env, &contract_id);
+    let admin = Address::generate(&env);
+    let xlm_token = Address::generate(&env);
+
+    env.ledger().with_mut(|li| li.timestamp = 1000);
+    initialize_with_mock_prices(&client, &admin);
+    client.set_stellar_asset(&xlm_token, &symbol_short!("XLM"));
+
+    env.ledger().with_mut(|li| li.timestamp = 2000);
+    client.set_price(&symbol_short!("XLM"), &13_0000000i128);
+
+    assert_eq!(client.base(), Asset::Other(Symbol::new(&env, "USD")));
+    assert_eq!(client.decimals(), 7u32);
+    assert_eq!(client.assets().len(), 6);
+    assert!(client.assets().contains(Asset::Stellar(xlm_token.clone())));
+
+    let xlm = Asset::Stellar(xlm_token);
+    let last = client.lastprice(&xlm).unwrap();
+    assert_eq!(last.price, 13_0000000i128);
+    assert_eq!(last.timestamp, 2000u64);
+
+    // Lookup by timestamp returns the observation at or before it
+    assert_eq!(client.price(&xlm, &1500u64).unwrap().price, 12_0000000i128);
+    assert_eq!(client.price(&xlm, &2500u64).unwrap().price, 13_0000000i128);
+    assert_eq!(client.price(&xlm, &500u64), None);
+
+    let records = client.prices(&xlm, &5u32).unwrap();
+    assert_eq!(records.len(), 2);
+    assert_eq!(records.get(0).unwrap().price, 13_0000000i128);
+
+    // Unknown assets resolve to no data
+    assert_eq!(client.lastprice(&Asset::Other(symbol_short!("BTC"))), None);
+    assert_eq!(client.lastprice(&Asset::Stellar(Address::generate(&env))), None);
+}
+
+#[test]
+fn test_price_history_ring_buffer() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = create_oracle_contract(&env);
+    let client = PriceOracleClient::new(&env, &contract_id);
+    let admin = Address::generate(&env);
+
+    initialize_with_mock_prices(&client, &admin);
+
+    let asset = symbol_short!("ETH");
+    client.set_history_size(&asset, &3u32);
+
+    for i in 1..=5u64 {
+        env.ledger().with_mut(|li| li.timestamp = i * 100);
+        client.set_price(&asset, &(i as i128 * 1000_0000000i128));
+    }
+
+    // Only the last three observations are kept
+    let history = client.get_price_history(&asset, &10u32);
+    assert_eq!(history.len(), 3);
+    assert_eq!(history.get(0).unwrap().price, 5000_0000000i128);
+    assert_eq!(history.get(2).unwrap().price, 3000_0000000i128);
+
+    assert_eq!(client.get_price_at(&asset, &450u64).price, 4000_0000000i128);
+    assert_eq!(client.get_price_at(&asset, &300u64).timestamp, 300u64);
+    assert!(client.try_get_price_at(&asset, &250u64).is_err());
+
+    // Growing the buffer keeps the existing observations in order
+    client.set_history_size(&asset, &4u32);
+    env.ledger().with_mut(|li| li.timestamp = 600);
+    client.set_price(&asset, &6000_0000000i128);
+
+    let history = client.get_price_history(&asset, &10u32);
+    assert_eq!(history.len(), 4);
+    assert_eq!(history.get(0).unwrap().price, 6000_0000000i128);
+    assert_eq!(history.get(3).unwrap().price, 3000_0000000i128);
+
+    // History is kept out of the instance entry
+    env.as_contract(&contract_id, || {
+        assert!(env.storage().persistent().has(&DataKey::ObservationState(asset.clone())));
+        assert!(env.storage().persistent().has(&DataKey::Observation(asset.clone(), 0)));
+        assert!(!env.storage().instance().has(&DataKey::Observation(asset.clone(), 0)));
+    });
+}
+
+#[test]
+fn test_derived_price() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = create_oracle_contract(&env);
+    let client = PriceOracleClient::new(&env, &contract_id);
+    let admin = Address::generate(&env);
+
+    initialize_with_mock_prices(&client, &admin);
+
+    let eth = symbol_short!("ETH");
+    let reth = symbol_short!("rETH");
+
+    env.ledger().with_mut(|li| li.timestamp = 1000);
+    client.set_price(&eth, &2000_0000000i128);
+    client.set_derived_asset(&reth, &eth, &1000u32); // 10% per year
+    client.set_ratio(&reth, &1_1000000i128);
+
+    // Bases must be registered and cannot become derived themselves
+    assert!(client.try_set_derived_asset(&symbol_short!("stETH"), &symbol_short!("DOGE"), &1000u32).is_err());
+    assert!(client.try_set_derived_asset(&eth, &symbol_short!("stETH"), &1000u32).is_err());
+
+    assert_eq!(client.get_price(&reth), 2200_0000000i128);
+    let prices = client.get_prices(&soroban_sdk::vec![&env, eth.clone(), reth.clone()]);
+    assert_eq!(prices.get(1).unwrap(), 2200_0000000i128);
+
+    // SEP-40 consumers see the derived price too
+    let last = client.lastprice(&Asset::Other(reth.clone())).unwrap();
+    assert_eq!(last.price, 2200_0000000i128);
+    assert_eq!(last.timestamp, 1000u64);
+
+    // The derived price follows the base price
+    client.set_price(&eth, &1000_0000000i128);
+    assert_eq!(client.get_price(&reth), 1100_0000000i128);
+
+    // Half a year allows at most 5% growth over the first reported ratio
+    env.ledger().with_mut(|li| li.timestamp = 1000 + 31_536_000 / 2);
+    client.set_price(&eth, &1000_0000000i128);
+    client.set_ratio(&reth, &1_1600000i128);
+    assert_eq!(client.get_price(&reth), 1155_0000000i128);
+    assert!(client.get_ratio_info(&reth).is_capped);
+
+    // Decreases (e.g. slashing) are always accepted
+    client.set_ratio(&reth, &1_0000000i128);
+    assert_eq!(client.get_ratio(&reth).ratio, 1_0000000i128);
+
+    // History follows the base observations, a ratio change replaces the latest one
+    let half_year = 1000 + 31_536_000 / 2;
+    let history = client.get_price_history(&reth, &10u32);
+    assert_eq!(history.len(), 2);
+    assert_eq!(history.get(0).unwrap(), PriceData { price: 1000_0000000i128, conf: 0, timestamp: half_year });
+    assert_eq!(history.get(1).unwrap().price, 1100_0000000i128);
+    assert_eq!(client.get_price_at(&reth, &(half_year - 1)).price, 1100_0000000i128);
+    assert_eq!(client.price(&Asset::Other(reth.clone()), &1000u64).unwrap().price, 1100_0000000i128);
+    assert_eq!(client.prices(&Asset::Other(reth.clone()), &5u32).unwrap().len(), 2);
+    assert_eq!(client.get_twap(&reth, &(31_536_000 / 2)), 1100_0000000i128);
+}
+
+#[test]
+fn test_fallback_source() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let client = PriceOracleClient::new(&env, &create_oracle_contract(&env));
+    let external_id = create_oracle_contract(&env);
+    let external = PriceOracleClient::new(&env, &external_id);
+    initialize_with_mock_prices(&client, &admin);
+    initialize_with_mock_prices(&external, &admin);
+
+    let eth = symbol_short!("ETH");
+    let external_source = PriceSource::Sep40(external_id.clone(), Asset::Other(eth.clone()));
+    client.set_price_sources(&eth, &soroban_sdk::vec![&env, PriceSource::Local, external_source.clone()]);
+
+    env.ledger().with_mut(|li| li.timestamp = 1000);
+    client.set_price(&eth, &2000_0000000i128);
+    external.set_price(&eth, &2100_0000000i128);
+
+    // The local feed is preferred while fresh
+    let sourced = client.get_sourced_price(&eth);
+    assert_eq!(sourced.price, 2000_0000000i128);
+    assert_eq!(sourced.source, PriceSource::Local);
+
+    // Once it goes stale the external oracle takes over
+    env.ledger().with_mut(|li| li.timestamp = 5000);
+    external.set_price(&eth, &2100_0000000i128);
+    let sourced = client.get_sourced_price(&eth);
+    assert_eq!(sourced.price, 2100_0000000i128);
+    assert_eq!(sourced.source, external_source);
+    assert!(client.is_price_fresh(&eth));
+
+    // The external feed's own outage is reported from its history
+    assert_eq!(client.get_price_status(&eth).resumed_at, 5000u64);
+    let info = client.get_update_info(&eth);
+    assert!(info.is_fresh);
+    assert_eq!(info.last_updated, 5000u64);
+
+    // Fresh sources more than the max spread apart are rejected
+    client.set_price(&eth, &2000_0000000i128);
+    client.set_max_spread(&eth, &100u32);
+    assert!(client.try_get_price(&eth).is_err());
+    client.set_max_spread(&eth, &1000u32);
+    assert_eq!(client.get_price(&eth), 2000_0000000i128);
+
+    // No fresh source at all
+    env.ledger().with_mut(|li| li.timestamp = 10000);
+    assert!(client.try_get_price(&eth).is_err());
+    assert!(!client.is_price_fresh(&eth));
+    assert!(!client.get_update_info(&eth).is_fresh);
+}
+
+#[test]
+fn test_register_asset() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let client = PriceOracleClient::new(&env, &create_oracle_contract(&env));
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+
+    // A fresh oracle has no assets and no prices
+    let eth = symbol_short!("ETH");
+    assert_eq!(client.get_assets().len(), 0);
+    assert!(client.try_get_price(&symbol_short!("stETH")).is_err());
+    assert!(client.try_set_price(&eth, &2000_0000000i128).is_err());
+
+    let description = String::from_str(&env, "Ether");
+    client.register_asset(&eth, &18u32, &description);
+    assert!(client.try_register_asset(&eth, &18u32, &description).is_err());
+
+    let info = client.get_asset_info(&eth);
+    assert_eq!(info.decimals, 18);
+    assert_eq!(info.description, description);
+    assert_eq!(client.get_assets(), soroban_sdk::vec![&env, eth.clone()]);
+
+    client.set_price(&eth, &2000_0000000i128);
+    assert_eq!(client.get_price_unchecked(&eth), 2000_0000000i128);
+}
+
+#[test]
+fn test_signed_prices() {
+    use ed25519_dalek::{Signer, SigningKey};
+    use soroban_sdk::{xdr::ToXdr, BytesN};
+
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = create_oracle_contract(&env);
+    let client = PriceOracleClient::new(&env, &contract_id);
+    let admin = Address::generate(&env);
+    initialize_with_mock_prices(&client, &admin);
+
+    let signing_key = SigningKey::from_bytes(&[7u8; 32]);
+    let publisher = BytesN::from_array(&env, &signing_key.verifying_key().to_bytes());
+    client.add_publisher(&publisher);
+
+    let sign_for = |oracle: &Address, updates: &Vec<SignedPriceUpdate>| {
+        let message = (oracle.clone(), env.ledger().network_id(), updates.clone()).to_xdr(&env);
+        let mut buf = [0u8; 512];
+        let len = message.len() as usize;
+        message.copy_into_slice(&mut buf[..len]);
+        BytesN::from_array(&env, &signing_key.sign(&buf[..len]).to_bytes())
+    };
+    let sign = |updates: &Vec<SignedPriceUpdate>| sign_for(&contract_id, updates);
+    let update = |asset: &str, price: i128, timestamp: u64, nonce: u64| SignedPriceUpdate {
+        asset: Symbol::new(&env, asset),
+        price,
+        conf: 0,
+        timestamp,
+        nonce,
+    };
+
+    env.ledger().with_mut(|li| li.timestamp = 1000);
+    let updates = soroban_sdk::vec![&env, update("ETH", 2000_0000000, 990, 1), update("XLM", 13_0000000, 990, 2)];
+    let signature = sign(&updates);
+
+    // Anyone can relay the batch
+    client.submit_signed_prices(&publisher, &updates, &signature);
+    assert_eq!(client.get_price(&symbol_short!("ETH")), 2000_0000000i128);
+    assert_eq!(client.get_price(&symbol_short!("XLM")), 13_0000000i128);
+    assert_eq!(client.get_publisher_nonce(&publisher), 2);
+
+    // The observation time is kept as the price timestamp
+    assert_eq!(client.get_price_with_confidence(&symbol_short!("ETH")).timestamp, 990u64);
+
+    // Replays, tampered payloads and old timestamps are rejected
+    assert!(client.try_submit_signed_prices(&publisher, &updates, &signature).is_err());
+    let tampered = soroban_sdk::vec![&env, update("ETH", 1_0000000, 990, 3)];
+    assert!(client.try_submit_signed_prices(&publisher, &tampered, &signature).is_err());
+    let old = soroban_sdk::vec![&env, update("ETH", 2000_0000000, 980, 3)];
+    assert!(client.try_submit_signed_prices(&publisher, &old, &sign(&old)).is_err());
+
+    // Batches signed for another deployment are rejected
+    let other = soroban_sdk::vec![&env, update("ETH", 2005_0000000, 995, 3)];
+    let other_oracle = create_oracle_contract(&env);
+    assert!(client.try_submit_signed_prices(&publisher, &other, &sign_for(&other_oracle, &other)).is_err());
+    client.submit_signed_prices(&publisher, &other, &sign(&other));
+    assert_eq!(client.get_price(&symbol_short!("ETH")), 2005_0000000i128);
+
+    // Unknown keys cannot publish
+    client.remove_publisher(&publisher);
+    let updates = soroban_sdk::vec![&env, update("ETH", 2010_0000000, 1000, 4)];
+    assert!(client.try_submit_signed_prices(&publisher, &updates, &sign(&updates)).is_err());
+}
+
+#[test]
+fn test_price_confidence() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let client = PriceOracleClient::new(&env, &create_oracle_contract(&env));
+    let admin = Address::generate(&env);
+    initialize_with_mock_prices(&client, &admin);
+
+    let steth = symbol_short!("stETH");
+    client.set_price_with_confidence(&steth, &1500_0000000i128, &15_0000000i128);
+
+    let price_data = client.get_price_with_confidence(&steth);
+    assert_eq!(price_data.price, 1500_0000000i128);
+    assert_eq!(price_data.conf, 15_0000000i128);
+    assert_eq!(client.get_price_history(&steth, &1u32).get(0).unwrap().conf, 15_0000000i128);
+    assert!(client.try_set_price_with_confidence(&steth, &1500_0000000i128, &1500_0000000i128).is_err());
+
+    // A 1% interval is rejected once the maximum is 0.5%
+    client.set_max_confidence(&steth, &50u32);
+    assert!(client.try_get_price(&steth).is_err());
+    assert!(client.try_get_price_with_confidence(&steth).is_err());
+
+    client.set_price_with_confidence(&steth, &1500_0000000i128, &5_0000000i128);
+    assert_eq!(client.get_price(&steth), 1500_0000000i128);
+}
+
+#[test]
+fn test_convert() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let client = PriceOracleClient::new(&env, &create_oracle_contract(&env));
+    let admin = Address::generate(&env);
+    initialize_with_mock_prices(&client, &admin);
+
+    let steth = symbol_short!("stETH");
+    let usdc = symbol_short!("USDC");
+
+    // 2 stETH (18 decimals) at $1500 = 3000 USDC (7 decimals)
+    let two_steth = 2_000_000_000_000_000_000i128;
+    assert_eq!(client.convert(&steth, &usdc, &two_steth), 3000_0000000i128);
+    assert_eq!(client.convert(&usdc, &steth, &3000_0000000i128), two_steth);
+
+    // Rounds down once: 1e-7 USDC is 66666666.67 wei of stETH
+    assert_eq!(client.convert(&usdc, &steth, &1i128), 66666666i128);
+    assert_eq!(client.convert(&steth, &usdc, &66666666i128), 0i128);
+}
+
+#[test]
+fn test_price_status() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let client = PriceOracleClient::new(&env, &create_oracle_contract(&env));
+    let admin = Address::generate(&env);
+
+    env.ledger().with_mut(|li| li.timestamp = 1000);
+    initialize_with_mock_prices(&client, &admin);
+
+    let steth = symbol_short!("stETH");
+    let status = client.get_price_status(&steth);
+    assert_eq!(status.price, 1500_0000000i128);
+    assert_eq!(status.timestamp, 1000u64);
+    assert!(status.is_fresh);
+    assert_eq!(status.source, PriceSource::Local);
+
+    // Stale prices are reported instead of panicking
+    env.ledger().with_mut(|li| li.timestamp = 1000 + 3601);
+    let status = client.get_price_status(&steth);
+    assert_eq!(status.price, 1500_0000000i128);
+    assert!(!status.is_fresh);
+    assert!(client.try_get_price(&steth).is_err());
+
+    let status = client.get_price_status(&symbol_short!("ETH"));
+    assert_eq!(status.price, 0i128);
+    assert!(!status.is_fresh);
+
+    // The first update after an outage is recorded
+    assert_eq!(client.get_resumed_at(&steth), 0u64);
+    client.set_price(&steth, &1400_0000000i128);
+    assert_eq!(client.get_resumed_at(&steth), 4601u64);
+    assert_eq!(client.get_price_status(&steth).resumed_at, 4601u64);
+
+    env.ledger().with_mut(|li| li.timestamp = 5000);
+    client.set_price(&steth, &1450_0000000i128);
+    assert_eq!(client.get_resumed_at(&steth), 4601u64);
+}
+
+#[test]
+fn test_ratio_cap() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let client = PriceOracleClient::new(&env, &create_oracle_contract(&env));
+    let admin = Address::generate(&env);
+    initialize_with_mock_prices(&client, &admin);
+
+    let eth = symbol_short!("ETH");
+    let reth = symbol_short!("rETH");
+
+    env.ledger().with_mut(|li| li.timestamp = 1000);
+    client.set_price(&eth, &2000_0000000i128);
+    client.set_derived_asset(&reth, &eth, &1000u32); // 10% per year
+    client.set_ratio(&reth, &1_1000000i128);
+    assert_eq!(client.get_ratio_snapshot(&reth), RatioSnapshot { ratio: 1_1000000i128, timestamp: 1000u64 });
+    assert!(client.try_set_ratio_snapshot(&reth, &1_1000000i128, &2000u64).is_err());
+
+    // A donation inflates the reported ratio by 2% within a day
+    env.ledger().with_mut(|li| li.timestamp = 1000 + 86_400);
+    client.set_price(&eth, &2000_0000000i128);
+    client.set_ratio(&reth, &1_1220000i128);
+
+    let info = client.get_ratio_info(&reth);
+    assert_eq!(info.reported, 1_1220000i128);
+    assert_eq!(info.max_ratio, 1_1003013i128);
+    assert_eq!(info.capped, 1_1003013i128);
+    assert!(info.is_capped);
+    assert_eq!(client.get_price(&reth), 2200_6026000i128);
+
+    // Refreshing moves the snapshot to the capped ratio, not the inflated one
+    client.refresh_ratio_snapshot(&reth);
+    let snapshot = client.get_ratio_snapshot(&reth);
+    assert_eq!(snapshot.ratio, 1_1003013i128);
+    assert_eq!(snapshot.timestamp, 1000 + 86_400);
+
+    // Reported ratios below the cap pass through unchanged
+    client.set_ratio(&reth, &1_1000000i128);
+    let info = client.get_ratio_info(&reth);
+    assert_eq!(info.capped, 1_1000000i128);
+    assert!(!info.is_capped);
+}
+
+#[test]
+fn test_pair_prices() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = create_oracle_contract(&env);
+    let client = PriceOracleClient::new(&env, &contract_id);
+    let admin = Address::generate(&env);
+
+    initialize_with_mock_prices(&client, &admin);
+
+    let steth = symbol_short!("stETH");
+    let reth = symbol_short!("rETH");
+    let eth = symbol_short!("ETH");
+    let btc = symbol_short!("BTC");
+    let usd = symbol_short!("USD");
+    client.set_price(&eth, &2000_0000000i128);
+    client.set_price(&btc, &40000_0000000i128);
+
+    client.register_pair(&steth, &eth, &18u32);
+    client.register_pair(&reth, &eth, &18u32);
+    client.register_pair(&reth, &steth, &18u32);
+    client.register_pair(&eth, &btc, &8u32);
+    assert!(client.try_register_pair(&steth, &usd, &7u32).is_err());
+    assert!(client.try_register_pair(&steth, &eth, &18u32).is_err());
+
+    let info = client.get_pair_info(&eth, &btc);
+    assert_eq!(info.quote, btc);
+    assert_eq!(info.decimals, 8u32);
+    assert_eq!(client.get_pairs().len(), 4);
+
+    // Stored pair prices keep their own decimals
+    client.set_pair_price(&steth, &eth, &990_000_000_000_000_000i128);
+    client.set_pair_price(&reth, &eth, &1_100_000_000_000_000_000i128);
+    let price = client.get_pair_price(&steth, &eth);
+    assert_eq!(price.price, 990_000_000_000_000_000i128);
+    assert_eq!(price.decimals, 18u32);
+
+    // Unrouted pairs without a stored price are unavailable
+    assert!(client.try_get_pair_price(&reth, &steth).is_err());
+
+    // rETH/stETH = rETH/ETH * ETH/stETH
+    client.set_pair_route(&reth, &steth, &eth);
+    assert_eq!(client.get_pair_route(&reth, &steth), eth);
+    assert_eq!(client.get_pair_price(&reth, &steth).price, 1_111_111_111_111_111_111i128);
+
+    // ETH/BTC = ETH/USD * USD/BTC
+    client.set_pair_route(&eth, &btc, &usd);
+    assert_eq!(client.get_pair_price(&eth, &btc).price, 5_000_000i128);
+    assert_eq!(client.get_pair_price(&eth, &usd).price, 2000_0000000i128);
+
+    // A stale USD leg makes the route unavailable
+    env.ledger().with_mut(|li| li.timestamp += 3601);
+    client.set_price(&eth, &2000_0000000i128);
+    assert_eq!(client.get_pair_price(&eth, &usd).price, 2000_0000000i128);
+    assert!(client.try_get_pair_price(&btc, &usd).is_err());
+    assert!(client.try_get_pair_price(&eth, &btc).is_err());
+}
+
+// Data of every alert event of a kind, oldest first
+fn alerts(env: &Env, kind: Symbol) -> soroban_sdk::Vec<Val> {
+    let mut found = soroban_sdk::vec![env];
+    for (_, topics, data) in env.events().all().iter() {
+        if topics.len() > 1
+            && Symbol::try_from_val(env, &topics.get(0).unwrap()).ok() == Some(symbol_short!("alert"))
+            && Symbol::try_from_val(env, &topics.get(1).unwrap()).ok() == Some(kind.clone())
+        {
+            found.push_back(data);
+        }
+    }
+    found
+}
+
+// Data of the latest alert event of a kind
+fn last_alert(env: &Env, kind: Symbol) -> Option<Val> {
+    alerts(env, kind).last()
+}
+
+#[test]
+fn test_alert_events() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = create_oracle_contract(&env);
+    let client = PriceOracleClient::new(&env, &contract_id);
+    let admin = Address::generate(&env);
+    let asset = symbol_short!("stETH");
+
+    env.ledger().with_mut(|li| li.timestamp = 1000);
+    initialize_with_mock_prices(&client, &admin);
+    client.set_alert_config(&asset, &500u32, &300u64);
+
+    // A 4% move stays quiet, a 10% move is reported
+    client.set_price(&asset, &1560_0000000i128);
+    assert!(last_alert(&env, symbol_short!("deviation")).is_none());
+    client.set_price(&asset, &1716_0000000i128);
+    let alert = DeviationAlert::try_from_val(&env, &last_alert(&env, symbol_short!("deviation")).unwrap()).unwrap();
+    assert_eq!(alert, DeviationAlert {
+        asset: asset.clone(),
+        old_price: 1560_0000000i128,
+        new_price: 1716_0000000i128,
+        deviation_bps: 1000u32,
+        threshold_bps: 500u32,
+    });
+
+    // Reads within 5 minutes of the 1 hour heartbeat are reported
+    env.ledger().with_mut(|li| li.timestamp = 1000 + 3000);
+    client.get_price(&asset);
+    assert!(last_alert(&env, symbol_short!("stale")).is_none());
+    env.ledger().with_mut(|li| li.timestamp = 1000 + 3400);
+    client.get_price(&asset);
+    let alert = StalenessAlert::try_from_val(&env, &last_alert(&env, symbol_short!("stale")).unwrap()).unwrap();
+    assert_eq!(alert.last_updated, 1000u64);
+    assert_eq!(alert.read_at, 4400u64);
+    assert_eq!(alert.max_age, 3600u64);
+    assert_eq!(alert.warning_secs, 300u64);
+
+    // Rounds filling up are quiet, losing the quorum of active feeders is reported
+    let feeder_a = Address::generate(&env);
+    let feeder_b = Address::generate(&env);
+    let feeder_c = Address::generate(&env);
+    client.add_feeder(&feeder_a);
+    client.add_feeder(&feeder_b);
+    client.add_feeder(&feeder_c);
+    client.set_quorum(&2u32);
+    client.submit_price(&feeder_a, &asset, &1720_0000000i128);
+    assert!(last_alert(&env, symbol_short!("quorum")).is_none());
+    client.submit_price(&feeder_b, &asset, &1720_0000000i128);
+    client.submit_price(&feeder_a, &asset, &1725_0000000i128);
+    assert!(last_alert(&env, symbol_short!("quorum")).is_none());
+
+    // Feeder b went quiet and its submission is now stale
+    env.ledger().with_mut(|li| li.timestamp += 3601);
+    client.submit_price(&feeder_a, &asset, &1730_0000000i128);
+    let alert = QuorumAlert::try_from_val(&env, &last_alert(&env, symbol_short!("quorum")).unwrap()).unwrap();
+    assert_eq!(alert, QuorumAlert {
+        asset: asset.clone(),
+        old_active_feeders: 2u32,
+        active_feeders: 1u32,
+        quorum: 2u32,
+    });
+
+    // Removing a feeder with a fresh submission can also lose the quorum
+    client.submit_price(&feeder_c, &asset, &1730_0000000i128);
+    assert_eq!(alerts(&env, symbol_short!("quorum")).len(), 1);
+    client.remove_feeder(&feeder_c);
+    assert_eq!(alerts(&env, symbol_short!("quorum")).len(), 2);
+    let alert = QuorumAlert::try_from_val(&env, &last_alert(&env, symbol_short!("quorum")).unwrap()).unwrap();
+    assert_eq!(alert.old_active_feeders, 2u32);
+    assert_eq!(alert.active_feeders, 1u32);
+}
@@ -4,6 +4,7 @@ use soroban_sdk::{
     contract, contractimpl, contracttype, Address, Env, String,
     symbol_short
 };
+use paralyx_math::{fixed_div_floor, fixed_mul_floor};
 
 pub(crate) const DAY_IN_LEDGERS: u32 = 17280;
 pub(crate) const INSTANCE_BUMP_AMOUNT: u32 = 7 * DAY_IN_LEDGERS;
@@ -157,13 +158,13 @@ impl SToken {
     /// Convert sToken amount to underlying asset amount
     pub fn s_token_to_underlying(env: Env, s_token_amount: i128) -> i128 {
         let rate = Self::exchange_rate(env.clone());
-        s_token_amount * rate / 10_000_000i128
+        fixed_mul_floor(s_token_amount, rate)
     }
 
     /// Convert underlying asset amount to sToken amount
     pub fn underlying_to_s_token(env: Env, underlying_amount: i128) -> i128 {
         let rate = Self::exchange_rate(env.clone());
-        underlying_amount * 10_000_000i128 / rate
+        fixed_div_floor(underlying_amount, rate)
     }
 
     /// Set bridge validator (admin only)
@@ -0,0 +1,169 @@
+#![no_std]
+
+//! Fixed-point math shared by the Paralyx contracts. Every division picks an
+//! explicit rounding direction and overflowing intermediates fall back to
+//! 256-bit arithmetic instead of wrapping.
+
+pub const SCALAR_7: i128 = 10_000_000; // 1.0 with 7 decimals
+pub const BPS_DENOMINATOR: i128 = 10_000; // 100% in basis points
+
+/// `a * b / c` rounded down, or `None` on overflow or division by zero
+pub fn checked_mul_div_floor(a: i128, b: i128, c: i128) -> Option<i128> {
+    let (quotient, remainder, negative) = mul_div_parts(a, b, c)?;
+    if negative {
+        let magnitude = if remainder > 0 { quotient.checked_add(1)? } else { quotient };
+        negate(magnitude)
+    } else {
+        i128::try_from(quotient).ok()
+    }
+}
+
+/// `a * b / c` rounded up, or `None` on overflow or division by zero
+pub fn checked_mul_div_ceil(a: i128, b: i128, c: i128) -> Option<i128> {
+    let (quotient, remainder, negative) = mul_div_parts(a, b, c)?;
+    if negative {
+        negate(quotient)
+    } else {
+        let magnitude = if remainder > 0 { quotient.checked_add(1)? } else { quotient };
+        i128::try_from(magnitude).ok()
+    }
+}
+
+/// `a * b / c` rounded down
+pub fn mul_div_floor(a: i128, b: i128, c: i128) -> i128 {
+    checked_mul_div_floor(a, b, c).unwrap_or_else(|| panic!("math overflow"))
+}
+
+/// `a * b / c` rounded up
+pub fn mul_div_ceil(a: i128, b: i128, c: i128) -> i128 {
+    checked_mul_div_ceil(a, b, c).unwrap_or_else(|| panic!("math overflow"))
+}
+
+/// Multiply by a 7 decimal fixed-point value, rounding down
+pub fn fixed_mul_floor(a: i128, b: i128) -> i128 {
+    mul_div_floor(a, b, SCALAR_7)
+}
+
+/// Multiply by a 7 decimal fixed-point value, rounding up
+pub fn fixed_mul_ceil(a: i128, b: i128) -> i128 {
+    mul_div_ceil(a, b, SCALAR_7)
+}
+
+/// Divide by a 7 decimal fixed-point value, rounding down
+pub fn fixed_div_floor(a: i128, b: i128) -> i128 {
+    mul_div_floor(a, SCALAR_7, b)
+}
+
+/// Divide by a 7 decimal fixed-point value, rounding up
+pub fn fixed_div_ceil(a: i128, b: i128) -> i128 {
+    mul_div_ceil(a, SCALAR_7, b)
+}
+
+/// Apply a basis-point ratio to an amount, rounding down
+pub fn bps_mul_floor(amount: i128, bps: u32) -> i128 {
+    mul_div_floor(amount, bps as i128, BPS_DENOMINATOR)
+}
+
+/// Apply a basis-point ratio to an amount, rounding up
+pub fn bps_mul_ceil(amount: i128, bps: u32) -> i128 {
+    mul_div_ceil(amount, bps as i128, BPS_DENOMINATOR)
+}
+
+/// Express `part / whole` in basis points, rounding down
+pub fn to_bps_floor(part: i128, whole: i128) -> i128 {
+    mul_div_floor(part, BPS_DENOMINATOR, whole)
+}
+
+/// Raise a 7 decimal fixed-point value to an integer power, rounding every
+/// step down (e.g. compounding a per-period rate factor)
+pub fn pow_floor(base: i128, exp: u64) -> i128 {
+    pow(base, exp, fixed_mul_floor)
+}
+
+/// Raise a 7 decimal fixed-point value to an integer power, rounding every
+/// step up
+pub fn pow_ceil(base: i128, exp: u64) -> i128 {
+    pow(base, exp, fixed_mul_ceil)
+}
+
+fn pow(mut base: i128, mut exp: u64, mul: fn(i128, i128) -> i128) -> i128 {
+    let mut result = SCALAR_7;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = mul(result, base);
+        }
+        exp >>= 1;
+        if exp > 0 {
+            base = mul(base, base);
+        }
+    }
+    result
+}
+
+fn negate(magnitude: u128) -> Option<i128> {
+    if magnitude == i128::MIN.unsigned_abs() {
+        Some(i128::MIN)
+    } else {
+        i128::try_from(magnitude).ok().map(|value| -value)
+    }
+}
+
+// Quotient, remainder and sign of `|a * b| / |c|`
+fn mul_div_parts(a: i128, b: i128, c: i128) -> Option<(u128, u128, bool)> {
+    if c == 0 {
+        return None;
+    }
+    let negative = (a < 0) ^ (b < 0) ^ (c < 0) && a != 0 && b != 0;
+    let divisor = c.unsigned_abs();
+
+    let (high, low) = wide_mul(a.unsigned_abs(), b.unsigned_abs());
+    let (quotient, remainder) = wide_div(high, low, divisor)?;
+    Some((quotient, remainder, negative))
+}
+
+// Full 256-bit product of two 128-bit values as (high, low) halves
+fn wide_mul(a: u128, b: u128) -> (u128, u128) {
+    if let Some(product) = a.checked_mul(b) {
+        return (0, product);
+    }
+
+    const MASK: u128 = u64::MAX as u128;
+    let (a_high, a_low) = (a >> 64, a & MASK);
+    let (b_high, b_low) = (b >> 64, b & MASK);
+
+    let low_low = a_low * b_low;
+    let high_low = a_high * b_low;
+    let low_high = a_low * b_high;
+    let high_high = a_high * b_high;
+
+    let middle = (low_low >> 64) + (high_low & MASK) + (low_high & MASK);
+    let low = (middle << 64) | (low_low & MASK);
+    let high = high_high + (high_low >> 64) + (low_high >> 64) + (middle >> 64);
+    (high, low)
+}
+
+// Divide a 256-bit value by a 128-bit divisor, `None` if the quotient overflows
+fn wide_div(high: u128, low: u128, divisor: u128) -> Option<(u128, u128)> {
+    if high == 0 {
+        return Some((low / divisor, low % divisor));
+    }
+    if high >= divisor {
+        return None;
+    }
+
+    // Restoring long division, one bit of the low half at a time
+    let mut remainder = high;
+    let mut quotient = 0u128;
+    for bit in (0..128).rev() {
+        let carry = remainder >> 127;
+        remainder = (remainder << 1) | ((low >> bit) & 1);
+        quotient <<= 1;
+        if carry == 1 || remainder >= divisor {
+            remainder = remainder.wrapping_sub(divisor);
+            quotient |= 1;
+        }
+    }
+    Some((quotient, remainder))
+}
+
+mod test;
@@ -0,0 +1,68 @@
+#![cfg(test)]
+
+use super::*;
+
+#[test]
+fn test_mul_div_rounding() {
+    assert_eq!(mul_div_floor(10, 1, 3), 3);
+    assert_eq!(mul_div_ceil(10, 1, 3), 4);
+    assert_eq!(mul_div_floor(9, 1, 3), 3);
+    assert_eq!(mul_div_ceil(9, 1, 3), 3);
+
+    // Negative results round towards negative and positive infinity
+    assert_eq!(mul_div_floor(-10, 1, 3), -4);
+    assert_eq!(mul_div_ceil(-10, 1, 3), -3);
+    assert_eq!(mul_div_floor(10, -1, -3), 3);
+    assert_eq!(mul_div_floor(0, -1, 3), 0);
+}
+
+#[test]
+fn test_mul_div_wide_intermediate() {
+    // 1B ETH in wei times a $1M price overflows i128 before the division
+    let amount = 1_000_000_000 * 1_000_000_000_000_000_000i128;
+    let price = 1_000_000 * SCALAR_7;
+    assert!(amount.checked_mul(price).is_none());
+    assert_eq!(fixed_mul_floor(amount, price), amount * 1_000_000);
+
+    assert_eq!(mul_div_floor(i128::MAX, i128::MAX, i128::MAX), i128::MAX);
+    assert_eq!(mul_div_floor(i128::MAX, 3, 7), 72917650054486813599294558735378902454);
+    assert_eq!(mul_div_ceil(i128::MAX, 3, 7), 72917650054486813599294558735378902455);
+    assert_eq!(mul_div_floor(i128::MIN, 1, 1), i128::MIN);
+}
+
+#[test]
+fn test_checked_overflow() {
+    assert_eq!(checked_mul_div_floor(i128::MAX, 2, 1), None);
+    assert_eq!(checked_mul_div_ceil(i128::MAX, i128::MAX, 1), None);
+    assert_eq!(checked_mul_div_floor(1, 1, 0), None);
+    assert_eq!(checked_mul_div_floor(i128::MIN, -1, 1), None);
+}
+
+#[test]
+#[should_panic(expected = "math overflow")]
+fn test_mul_div_overflow_panics() {
+    mul_div_floor(i128::MAX, 2, 1);
+}
+
+#[test]
+fn test_fixed_point_and_bps() {
+    assert_eq!(fixed_mul_floor(3_0000000, 1_5000000), 4_5000000);
+    assert_eq!(fixed_div_floor(1_0000000, 3_0000000), 3333333);
+    assert_eq!(fixed_div_ceil(1_0000000, 3_0000000), 3333334);
+
+    assert_eq!(bps_mul_floor(999, 5000), 499);
+    assert_eq!(bps_mul_ceil(999, 5000), 500);
+    assert_eq!(to_bps_floor(1, 3), 3333);
+}
+
+#[test]
+fn test_pow() {
+    assert_eq!(pow_floor(1_0500000, 0), SCALAR_7);
+    assert_eq!(pow_floor(1_0500000, 1), 1_0500000);
+    assert_eq!(pow_floor(1_1000000, 2), 1_2100000);
+    assert_eq!(pow_floor(2_0000000, 10), 1024_0000000);
+
+    // 1.0000001^2 = 1.00000020000001, truncated to 7 decimals either way
+    assert_eq!(pow_floor(1_0000001, 2), 1_0000002);
+    assert_eq!(pow_ceil(1_0000001, 2), 1_0000003);
+}
@@ -55,7 +55,7 @@ echo "  sToken XLM:   $STOKEN_XLM_ID"
 echo "  Lending Pool: $LENDING_POOL_ID"
 echo ""
 echo "🔧 Next steps:"
-echo "1. Initialize Price Oracle"
+echo "1. Initialize Price Oracle and register its assets"
 echo "2. Initialize sToken"  
 echo "3. Initialize Lending Pool"
 echo "4. Add XLM asset to Lending Pool"
@@ -65,6 +65,7 @@ echo "💡 Use the following commands to initialize:"
 echo ""
 echo "# Initialize Price Oracle"
 echo "stellar contract invoke --id $PRICE_ORACLE_ID --source $SOURCE_ACCOUNT --network $NETWORK -- initialize --admin [YOUR_ADDRESS]"
+echo "stellar contract invoke --id $PRICE_ORACLE_ID --source $SOURCE_ACCOUNT --network $NETWORK -- register_asset --asset XLM --decimals 7 --description \"Stellar Lumens\""
 echo ""
 echo "# Initialize sToken"
 echo "stellar contract invoke --id $STOKEN_XLM_ID --source $SOURCE_ACCOUNT --network $NETWORK -- initialize --admin [YOUR_ADDRESS] --lending_pool $LENDING_POOL_ID --underlying_asset [XLM_TOKEN_ADDRESS] --name \"Paralyx XLM\" --symbol \"pXLM\" --decimals 7"